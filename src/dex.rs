@@ -0,0 +1,249 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+};
+use borsh::{BorshSerialize, BorshDeserialize};
+use crate::{errors::FundError, utils::transfer_from_vault};
+
+// DEX tags stored per leg in InvestmentProposalAccount::dex_tags
+pub const DEX_JUPITER: u8 = 0;
+pub const DEX_ORCA_WHIRLPOOL: u8 = 1;
+
+pub fn is_supported_dex(dex_tag: u8) -> bool {
+    dex_tag == DEX_JUPITER || dex_tag == DEX_ORCA_WHIRLPOOL
+}
+
+// Bits a Fund's dex_allowlist may set, bit n allows the DEX tagged n
+pub const SUPPORTED_DEX_MASK: u32 = (1 << DEX_JUPITER) | (1 << DEX_ORCA_WHIRLPOOL);
+
+pub fn is_dex_allowed(dex_allowlist: u32, dex_tag: u8) -> bool {
+    is_supported_dex(dex_tag) && dex_allowlist & (1 << dex_tag) != 0
+}
+
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+pub const JUPITER_EVENT_AUTHORITY: Pubkey = pubkey!("D8cy77BBepLMngZx6ZukaTff5hCt1HrWyKk3Hnd9oitf");
+
+// Anchor discriminator of Jupiter v6 `route`
+const JUPITER_ROUTE_DISCRIMINATOR: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+// Anchor discriminator of Whirlpool `swap`
+const ORCA_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+// Pool accounts of an Orca leg, in order: Whirlpool, Token Vault A, Token Vault B,
+// Tick Array 0, Tick Array 1, Tick Array 2, Oracle
+pub const ORCA_POOL_ACCOUNTS: usize = 7;
+
+// Offsets into the Whirlpool and TickArray account data (after the 8-byte Anchor discriminator)
+const WHIRLPOOL_FEE_RATE_OFFSET: usize = 45;
+const WHIRLPOOL_SQRT_PRICE_OFFSET: usize = 65;
+const WHIRLPOOL_TOKEN_MINT_A_OFFSET: usize = 101;
+const WHIRLPOOL_TOKEN_VAULT_A_OFFSET: usize = 133;
+const WHIRLPOOL_TOKEN_MINT_B_OFFSET: usize = 181;
+const WHIRLPOOL_TOKEN_VAULT_B_OFFSET: usize = 213;
+const TICK_ARRAY_WHIRLPOOL_OFFSET: usize = 9956;
+
+// Off-chain computed route for one leg, supplied by the executor
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SwapRoute {
+    pub quoted_out_amount: u64,
+    pub slippage_bps: u16,
+    pub num_accounts: u8,
+    // Borsh-encoded Vec<RoutePlanStep> as returned by the Jupiter quote API
+    pub route_plan: Vec<u8>,
+}
+
+// Builds a Jupiter v6 `route` instruction with the vault PDA as token authority
+pub fn build_jupiter_swap_ix(
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    destination_token_account: &Pubkey,
+    destination_mint: &Pubkey,
+    route_accounts: &[AccountInfo],
+    in_amount: u64,
+    route: &SwapRoute,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*vault, true),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new(*destination_token_account, false),
+        // Optional destination and platform fee accounts are passed as the program id when unused
+        AccountMeta::new_readonly(JUPITER_PROGRAM_ID, false),
+        AccountMeta::new_readonly(*destination_mint, false),
+        AccountMeta::new_readonly(JUPITER_PROGRAM_ID, false),
+        AccountMeta::new_readonly(JUPITER_EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(JUPITER_PROGRAM_ID, false),
+    ];
+    accounts.extend(route_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    let mut data = JUPITER_ROUTE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&route.route_plan);
+    data.extend_from_slice(&in_amount.to_le_bytes());
+    data.extend_from_slice(&route.quoted_out_amount.to_le_bytes());
+    data.extend_from_slice(&route.slippage_bps.to_le_bytes());
+    data.push(0); // platform_fee_bps
+
+    Instruction {
+        program_id: JUPITER_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    data.get(offset..offset + 32)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Pubkey::new_from_array)
+        .ok_or_else(|| FundError::InvalidAccountData.into())
+}
+
+// Checks the pool accounts of an Orca leg against the leg's mints and returns the swap
+// direction (true when swapping token A for token B)
+pub fn orca_swap_direction(pool_accounts: &[AccountInfo], from_mint: &Pubkey, to_mint: &Pubkey) -> Result<bool, ProgramError> {
+    let [whirlpool, token_vault_a, token_vault_b, tick_array_0, tick_array_1, tick_array_2, oracle] = pool_accounts else {
+        return Err(FundError::InvalidAccountData.into());
+    };
+    if *whirlpool.owner != ORCA_WHIRLPOOL_PROGRAM_ID {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Whirlpool's vaults must be the ones passed and its mints must be the leg's mints
+    let whirlpool_data = whirlpool.data.borrow();
+    let mint_a = read_pubkey(&whirlpool_data, WHIRLPOOL_TOKEN_MINT_A_OFFSET)?;
+    let mint_b = read_pubkey(&whirlpool_data, WHIRLPOOL_TOKEN_MINT_B_OFFSET)?;
+    if *token_vault_a.key != read_pubkey(&whirlpool_data, WHIRLPOOL_TOKEN_VAULT_A_OFFSET)? ||
+       *token_vault_b.key != read_pubkey(&whirlpool_data, WHIRLPOOL_TOKEN_VAULT_B_OFFSET)? {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    let a_to_b = if *from_mint == mint_a && *to_mint == mint_b {
+        true
+    } else if *from_mint == mint_b && *to_mint == mint_a {
+        false
+    } else {
+        return Err(FundError::InvalidTokenAccount.into());
+    };
+
+    // Tick arrays must belong to this Whirlpool
+    for tick_array in [tick_array_0, tick_array_1, tick_array_2] {
+        if *tick_array.owner != ORCA_WHIRLPOOL_PROGRAM_ID ||
+           read_pubkey(&tick_array.data.borrow(), TICK_ARRAY_WHIRLPOOL_OFFSET)? != *whirlpool.key {
+            return Err(FundError::InvalidAccountData.into());
+        }
+    }
+
+    let (oracle_pda, _bump) = Pubkey::find_program_address(&[b"oracle", whirlpool.key.as_ref()], &ORCA_WHIRLPOOL_PROGRAM_ID);
+    if *oracle.key != oracle_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    Ok(a_to_b)
+}
+
+// Expected output of swapping `in_amount` through a Whirlpool at its current price, net of the
+// pool fee. Price impact across ticks is ignored, so this is a quote for setting min_out, not a
+// bound. Pool accounts must already be checked with `orca_swap_direction`.
+pub fn orca_quote(pool_accounts: &[AccountInfo], in_amount: u64, a_to_b: bool) -> Result<u64, ProgramError> {
+    let whirlpool_data = pool_accounts[0].data.borrow();
+    let fee_rate = whirlpool_data.get(WHIRLPOOL_FEE_RATE_OFFSET..WHIRLPOOL_FEE_RATE_OFFSET + 2)
+        .and_then(|bytes| <[u8; 2]>::try_from(bytes).ok())
+        .map(u16::from_le_bytes)
+        .ok_or(FundError::InvalidAccountData)?;
+    let sqrt_price = whirlpool_data.get(WHIRLPOOL_SQRT_PRICE_OFFSET..WHIRLPOOL_SQRT_PRICE_OFFSET + 16)
+        .and_then(|bytes| <[u8; 16]>::try_from(bytes).ok())
+        .map(u128::from_le_bytes)
+        .filter(|&sqrt_price| sqrt_price > 0)
+        .ok_or(FundError::InvalidAccountData)?;
+
+    // Fee rate is in hundredths of a basis point
+    let amount = in_amount as u128 - in_amount as u128 * fee_rate as u128 / 1_000_000;
+
+    // sqrt_price is Q64.64 of token B per token A
+    let out = if a_to_b {
+        mul_shift_64(amount, sqrt_price).and_then(|value| mul_shift_64(value, sqrt_price))
+    } else {
+        ((amount << 64) / sqrt_price).checked_mul(1 << 64).map(|value| value / sqrt_price)
+    };
+    out.and_then(|value| u64::try_from(value).ok())
+        .ok_or_else(|| FundError::ArithmeticOverflow.into())
+}
+
+// (a * b) >> 64 without overflowing the intermediate product
+fn mul_shift_64(a: u128, b: u128) -> Option<u128> {
+    let high = a.checked_mul(b >> 64)?;
+    let low = a.checked_mul(b & u64::MAX as u128)? >> 64;
+    high.checked_add(low)
+}
+
+// Builds a Whirlpool `swap` instruction with the vault PDA as token authority.
+// Pool accounts must already be checked with `orca_swap_direction`.
+pub fn build_orca_swap_ix(
+    vault: &Pubkey,
+    source_token_account: &Pubkey,
+    destination_token_account: &Pubkey,
+    pool_accounts: &[AccountInfo],
+    in_amount: u64,
+    min_out_amount: u64,
+    a_to_b: bool,
+) -> Instruction {
+    let (token_owner_account_a, token_owner_account_b) = if a_to_b {
+        (source_token_account, destination_token_account)
+    } else {
+        (destination_token_account, source_token_account)
+    };
+
+    let accounts = vec![
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*vault, true),
+        AccountMeta::new(*pool_accounts[0].key, false),
+        AccountMeta::new(*token_owner_account_a, false),
+        AccountMeta::new(*pool_accounts[1].key, false),
+        AccountMeta::new(*token_owner_account_b, false),
+        AccountMeta::new(*pool_accounts[2].key, false),
+        AccountMeta::new(*pool_accounts[3].key, false),
+        AccountMeta::new(*pool_accounts[4].key, false),
+        AccountMeta::new(*pool_accounts[5].key, false),
+        AccountMeta::new_readonly(*pool_accounts[6].key, false),
+    ];
+
+    let mut data = ORCA_SWAP_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&in_amount.to_le_bytes());
+    data.extend_from_slice(&min_out_amount.to_le_bytes());
+    data.extend_from_slice(&0_u128.to_le_bytes()); // sqrt_price_limit, zero means no limit
+    data.push(1); // amount_specified_is_input
+    data.push(a_to_b as u8);
+
+    Instruction {
+        program_id: ORCA_WHIRLPOOL_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+// Wraps `lamports` of native SOL held by the program-owned Vault PDA (direct transfers) into the
+// vault's WSOL account so swaps can spend them. The Vault keeps enough lamports to stay
+// rent-exempt. Lamports are moved directly since the program owns the Vault, then sync_native
+// brings the WSOL balance up to date.
+pub fn wrap_sol<'a>(
+    vault_account_info: &AccountInfo<'a>,
+    vault_wsol_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    transfer_from_vault(vault_account_info, vault_wsol_info, lamports)?;
+
+    invoke(
+        &spl_token::instruction::sync_native(token_program_info.key, vault_wsol_info.key)?,
+        &[vault_wsol_info.clone(), token_program_info.clone()],
+    )
+}
@@ -1,18 +1,95 @@
-use solana_program::program_error::ProgramError;
-
-#[derive(Debug)]
-pub enum FundError {
-    InstructionUnpackError,
-    MissingRequiredSignature,
-    InvalidAccountData,
-    InvalidGovernanceMint,
-    AlreadyVoted,
-    VotingCeased,
-    InvalidTokenAccount,
-    InvalidInstruction,
-    NotEnoughFunds,
-}
-
-impl From<FundError> for ProgramError {
-    fn from(e: FundError) -> Self { ProgramError::Custom(e as u32) }
-}
\ No newline at end of file
+use num_derive::FromPrimitive;
+use solana_program::{decode_error::DecodeError, program_error::ProgramError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum FundError {
+    InstructionUnpackError,
+    MissingRequiredSignature,
+    InvalidAccountData,
+    InvalidGovernanceMint,
+    AlreadyVoted,
+    VotingCeased,
+    InvalidTokenAccount,
+    InvalidInstruction,
+    NotEnoughFunds,
+    ProposalNotPassed,
+    ProposalAlreadyExecuted,
+    QuorumNotReached,
+    AlreadyMember,
+    MemberNotFound,
+    CannotRemoveCreator,
+    NotAFundMember,
+    InvalidFundConfig,
+    InsufficientStake,
+    MintNotAccepted,
+    FundPaused,
+    InvalidDeadline,
+    ProposalStillActive,
+    InsufficientVaultFunds,
+    VotingStillOpen,
+    DepositCapExceeded,
+    LockupActive,
+    OutstandingProposals,
+    InvalidMemberCount,
+    UnsupportedDex,
+    SlippageExceeded,
+    ArithmeticOverflow,
+    InvalidDelegate,
+    PrivateFund,
+    FundFull,
+    ProposalCancelled,
+    ProposalHasVotes,
+    AccountNotInitialized,
+    TokenAccountNotEmpty,
+    DepositsFrozen,
+    InsufficientPermissions,
+    InvalidOracleAccount,
+    StaleOraclePrice,
+    ExecutionDelayActive,
+    StaleNonce,
+    TooManyOpenProposals,
+    AlreadyMigrated,
+    InvalidProposalTarget,
+    StaleVaultValue,
+    DepositTooSmall,
+    FormerMemberBalances,
+    StakeNotWithdrawn,
+    SwapOverspent,
+}
+
+impl From<FundError> for ProgramError {
+    fn from(e: FundError) -> Self { ProgramError::Custom(e as u32) }
+}
+
+impl DecodeError<FundError> for FundError {
+    fn type_of() -> &'static str { "FundError" }
+}
+
+impl FundError {
+    // Recovers the FundError behind a ProgramError returned by this program, if there is one
+    pub fn decode(error: &ProgramError) -> Option<FundError> {
+        match error {
+            ProgramError::Custom(code) => FundError::decode_custom_error_to_enum(*code),
+            _ => None,
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_recovers_the_fund_error() {
+        let error: ProgramError = FundError::QuorumNotReached.into();
+        assert_eq!(FundError::decode(&error), Some(FundError::QuorumNotReached));
+
+        let last: ProgramError = FundError::StaleVaultValue.into();
+        assert_eq!(FundError::decode(&last), Some(FundError::StaleVaultValue));
+    }
+
+    #[test]
+    fn decode_ignores_other_errors() {
+        assert_eq!(FundError::decode(&ProgramError::InvalidArgument), None);
+        assert_eq!(FundError::decode(&ProgramError::Custom(u32::MAX)), None);
+    }
+}
@@ -0,0 +1,24 @@
+use solana_program::{msg, pubkey::Pubkey};
+
+// Machine-parseable log lines for off-chain indexers, one per successful state change.
+// Format: EVENT:<KIND>:<field>:<field>:...
+
+pub fn emit_deposit(fund: &Pubkey, member: &Pubkey, amount: u64) {
+    msg!("EVENT:DEPOSIT:{}:{}:{}", fund, member, amount);
+}
+
+pub fn emit_proposal_created(fund: &Pubkey, proposal: &Pubkey, proposer: &Pubkey, proposal_kind: u8, deadline: i64) {
+    msg!("EVENT:PROPOSAL_CREATED:{}:{}:{}:{}:{}", fund, proposal, proposer, proposal_kind, deadline);
+}
+
+pub fn emit_vote(fund: &Pubkey, proposal: &Pubkey, voter: &Pubkey, vote: u8, voting_power: u64) {
+    msg!("EVENT:VOTE:{}:{}:{}:{}:{}", fund, proposal, voter, vote, voting_power);
+}
+
+pub fn emit_execute(fund: &Pubkey, proposal: &Pubkey, executor: &Pubkey, proposal_kind: u8) {
+    msg!("EVENT:EXECUTE:{}:{}:{}:{}", fund, proposal, executor, proposal_kind);
+}
+
+pub fn emit_donation(fund: &Pubkey, donor: &Pubkey, amount: u64) {
+    msg!("EVENT:DONATION:{}:{}:{}", fund, donor, amount);
+}
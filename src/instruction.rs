@@ -1,250 +1,1007 @@
-use solana_program::{
-    program_error::ProgramError,
-    pubkey::{Pubkey, PUBKEY_BYTES},
-};
-use crate::errors::FundError;
-use borsh::{BorshSerialize, BorshDeserialize};
-
-const BYTE_SIZE_8: usize = 8;
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub enum FundInstruction {
-
-    // 1. Governance Mint Account
-    // 2. Vault Account
-    // 3. Fund Account
-    // 4. System Program
-    // 5. Token Program
-    // 6. Rent Account
-    // 7. [..] Array of Fund Members
-    InitFundAccount { 
-        privacy: u8,
-        fund_name: String,
-    },
-
-    InitUserAccount { },
-
-    AddFundMember {
-        fund_name: String,
-    },
-
-    // 1. Governance Mint Account
-    // 2. Vault Account
-    // 3. Fund Account
-    // 4. System Program
-    // 5. Token Program
-    // 6. Member's Governance Token Account
-    // 7. Member's Wallet
-    // 8. User-specific PDA
-    InitDepositSol {
-        amount: u64,
-        fund_name: String,
-    },
-
-    InitDepositToken {
-        amount: u64,
-        fund_name: String,
-    },
-
-    // Proposals can be of the following types:
-    // 1. Investment -> tag 0
-    // 2. Addition of New Member -> tag 1
-    // 3. Removal of any member -> tag 2
-    // 4. Withdrawl -> tag 3
-
-    // 1. Proposer Account
-    // 2. [..] From Assets Mints
-    // 3. [..] To Assets Mints
-    InitProposalInvestment {
-        amounts: Vec<u64>,
-        // dex_tags: Vec<u8>,
-        deadline: i64,
-        fund_name: String,
-    },
-
-    // 1. Voter Account
-    // 2. Vote Account
-    // 3. Proposal Account
-    // 4. System Program
-    // 5. User PDA Account
-    // 6. Fund Account
-    // 7. Governance Mint Account
-    // 8. Voter Governance Token Account
-    Vote {
-        vote: u8,
-        fund_name: Vec<u8>,
-    },
-
-    DeleteFund {},
-
-    InitRentAccount { },
-
-    // 1. Proposal Account
-    // 2. Fund Account
-    // 3. Vault Account
-    // 4. System Program
-    // 5. Token Program
-    ExecuteProposalInvestment {},
-    Execute { proposal: Pubkey },
-    LeaveFund{fund_name: String },
-}
-
-impl FundInstruction {
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (tag, rest) = input
-            .split_first()
-            .ok_or(FundError::InstructionUnpackError)?;
-
-        Ok(match tag {
-            0 => {
-                let (privacy, rest) = Self::unpack_members(rest)?;
-                // let (fund_name, _rest) = Self::unpack_seed(rest)?;
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-                Self::InitFundAccount {
-                    privacy,
-                    fund_name,
-                }
-            }
-            1 => {
-                let (amount, rest) = Self::unpack_amount(rest)?;
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-                Self::InitDepositSol {
-                    amount,
-                    fund_name,
-                }
-            } 
-            2 => {
-                let (&num_of_swaps, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
-                let (amounts, rest) = Self::unpack_amounts(rest, num_of_swaps)?;
-                // let (dex_tags, rest) = Self::unpack_dex_tags(rest, num_of_swaps)?;
-                let (deadline, rest) = Self::unpack_deadline(rest)?;
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-
-                Self::InitProposalInvestment {
-                    amounts,
-                    // dex_tags,
-                    deadline,
-                    fund_name,
-                }
-            }
-            3 => {
-                let (&vote, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
-                let (fund_name, _rest) = Self::unpack_seed(rest)?;
-                Self::Vote {
-                    vote,
-                    fund_name,
-                }
-            }
-            4 => {
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-                Self::AddFundMember { fund_name }
-            }
-            5 => {
-                Self::ExecuteProposalInvestment {}
-            }
-            6 => {
-                Self::InitRentAccount {  }
-            }
-            7 => {
-                Self::InitUserAccount {  }
-            }
-            8 => {
-                let (amount, rest) = Self::unpack_amount(rest)?;
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-                Self::InitDepositToken {
-                    amount,
-                    fund_name,
-                }
-            }
-            9 => {
-                Self::DeleteFund { }
-            }
-            10 => {
-                let fund_name = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
-
-                Self::LeaveFund { fund_name }
-            }
-            _ => {
-                return Err(FundError::InstructionUnpackError.into());
-            }
-        })
-
-    }
-
-    fn unpack_members(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
-        let (&num, rest) = input
-            .split_first()
-            .ok_or(FundError::InstructionUnpackError)?;
-
-        Ok((num, rest))
-    }
-
-    fn unpack_seed(input: &[u8]) -> Result<(Vec<u8>, &[u8]), ProgramError> {
-        if input.len() < PUBKEY_BYTES {
-            return Err(FundError::InstructionUnpackError.into());
-        }
-
-        let mut seed: Vec<u8> = Vec::new();
-        let mut input_slice = input;
-        for _i in 0..PUBKEY_BYTES {
-            let (byte, rest) = input_slice.split_first().ok_or(FundError::InstructionUnpackError)?;
-            seed.push(*byte);
-            input_slice = rest;
-        }
-
-        Ok((seed, input_slice))
-    }
-
-    fn unpack_amount(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
-        if input.len() < BYTE_SIZE_8 {
-            return Err(FundError::InstructionUnpackError.into());
-        }
-        let (amount_bytes, rest) = input.split_at(BYTE_SIZE_8);
-
-        let amount = u64::from_le_bytes(amount_bytes.try_into().expect("Invalid amount length"));
-
-        Ok((amount, rest))
-    }
-
-    fn unpack_amounts(input: &[u8], num_of_swaps: u8) -> Result<(Vec<u64>, &[u8]), ProgramError> {
-        if input.len() < BYTE_SIZE_8*(num_of_swaps as usize) {
-            return Err(FundError::InstructionUnpackError.into());
-        }
-
-        let mut amounts: Vec<u64> = Vec::new();
-        let mut input_slice = input;
-        for _i in 0..num_of_swaps {
-            let (amount, rest) = Self::unpack_amount(input_slice)?;
-            amounts.push(amount);
-            input_slice = rest;
-        }
-
-        Ok((amounts, input_slice))
-    }
-
-    fn unpack_dex_tags(input: &[u8], num_of_swaps: u8) -> Result<(Vec<u8>, &[u8]), ProgramError> {
-        if input.len() < num_of_swaps as usize {
-            return Err(FundError::InstructionUnpackError.into());
-        }
-
-        let mut dex_tags: Vec<u8> = Vec::new();
-        let mut input_slice = input;
-        for _i in 0..num_of_swaps {
-            let (dex_tag, rest) = input_slice.split_first().ok_or(FundError::InstructionUnpackError)?;
-            dex_tags.push(*dex_tag);
-            input_slice = rest;
-        }
-
-        Ok((dex_tags, input_slice))
-    }
-
-    fn unpack_deadline(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
-        if input.len() < BYTE_SIZE_8 {
-            return Err(FundError::InstructionUnpackError.into());
-        }
-
-        let (deadline_data, rest) = input.split_at(BYTE_SIZE_8);
-        let deadline = i64::from_le_bytes(deadline_data.try_into().expect("Failed to get Deadline"));
-        Ok((deadline, rest))
-    }
-}
+use solana_program::{
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN, PUBKEY_BYTES},
+};
+use crate::{
+    dex::SwapRoute,
+    errors::FundError,
+    state::{ConfigChange, FundConfig, PROPOSAL_KIND_ADD_MEMBER, PROPOSAL_KIND_CONFIG_CHANGE, PROPOSAL_KIND_INVESTMENT, PROPOSAL_KIND_REMOVE_MEMBER, PROPOSAL_KIND_SET_ROLES, PROPOSAL_KIND_WITHDRAWAL},
+};
+use borsh::{BorshSerialize, BorshDeserialize};
+
+const BYTE_SIZE_8: usize = 8;
+
+// Upper bound on asset legs in one investment proposal, keeps allocation and execution bounded
+pub const MAX_PROPOSAL_LEGS: u8 = 8;
+
+// Upper bound on proposals voted on in one BatchVote, each costs a PDA creation within one
+// transaction's compute budget
+pub const MAX_BATCH_VOTES: u8 = 5;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum FundInstruction {
+
+    // 1. Governance Mint Account
+    // 2. Vault Account
+    // 3. System Program
+    // 4. Token Program
+    // 5. Fund Account
+    // 6. Creator's Wallet, pays the rent of every account created here
+    // 7. Governance Mint Metadata Account
+    // 8. Rent Sysvar
+    // 9. Token Metadata Program
+    // 10. Creator's User PDA
+    // 11. Creator's User-specific PDA
+    // 12. [..] (Mint Account, Pyth Price Account) per mint accepted for deposits, WSOL with the
+    //     SOL/USD feed (defaults to WSOL when none are given)
+    InitFundAccount { 
+        privacy: u8,
+        name: [u8; 32],
+        config: FundConfig,
+        fund_name: String,
+    },
+
+    // 1. User's Wallet
+    // 2. User PDA, created if missing
+    // 3. System Program
+    // 4. Fund Account
+    // 5. User-specific PDA for the Fund, created inactive until the user joins
+    InitUserAccount {
+        user_seed: String,
+    },
+
+    // 1. Fund Account
+    // 2. New Member's Wallet, pays for its own accounts, the Fund's growth and the rent share
+    // 3. System Program
+    // 4. New Member's User PDA
+    // 5. New Member's User-specific PDA
+    // 6. [..] Existing Members' Wallets, in the Fund's member order, receiving the rent share
+    AddFundMember {
+        fund_name: String,
+    },
+
+    // 1. Governance Mint Account
+    // 2. Vault Account
+    // 3. Fund Account
+    // 4. System Program
+    // 5. Token Program
+    // 6. Member's Governance Token Account
+    // 7. Member's Wallet
+    // 8. User-specific PDA
+    InitDepositSol {
+        amount: u64,
+        fund_name: String,
+    },
+
+    // 1. Depositor's Wallet
+    // 2. Depositor's Token Account for the deposited mint
+    // 3. Vault Account
+    // 4. Vault's Token Account for the deposited mint
+    // 5. Deposited Mint Account
+    // 6. Token Program
+    // 7. Associated Token Program
+    // 8. Fund Account
+    // 9. User-specific PDA
+    // 10. System Program
+    // 11. Rent Sysvar
+    // 12. Depositor's Governance Token Account
+    // 13. Governance Mint Account
+    // 14. Fee Collector's Token Account (only when the Fund charges a deposit fee)
+    // 15. Fund's Reserve WSOL Account, created on the first deposit with a reserve share (WSOL only)
+    //     or Pyth SOL/USD Price Account and the Deposited Mint's Pyth Price Account (other mints)
+    InitDepositToken {
+        amount: u64,
+        fund_name: String,
+    },
+
+    // Proposals can be of the following types (proposal_kind byte right after the tag):
+    // 1. Investment -> tag 0
+    // 2. Addition of New Member -> tag 1
+    // 3. Removal of any member -> tag 2
+    // 4. Withdrawl -> tag 3
+    // 5. Change of Fund settings -> tag 4
+
+    // 1. Proposer Account
+    // 2. Proposer's User-specific PDA
+    // 3. Fund Account
+    // 4. Proposal Account
+    // 5. System Program
+    // 6. Governance Mint Account
+    // 7. Fund's Proposal Registry PDA
+    // 8. [..] From Assets Mints
+    // 9. [..] To Assets Mints
+    InitProposalInvestment {
+        proposal_kind: u8,
+        amounts: Vec<u64>,
+        min_out: Vec<u64>,
+        dex_tags: Vec<u8>,
+        deadline: i64,
+        fund_name: String,
+    },
+
+    // 1. Voter Account
+    // 2. Vote Account
+    // 3. Proposal Account
+    // 4. System Program
+    // 5. User PDA Account
+    // 6. Fund Account
+    // 7. Governance Mint Account
+    // 8. Voter Governance Token Account
+    // 9. [..] Per member delegating to the voter: Delegator's User-specific PDA and Vote PDA
+    Vote {
+        vote: u8,
+        fund_name: String,
+    },
+
+    DeleteFund {},
+
+    // 1. Proposal Account
+    // 2. Fund Account
+    // 3. Vault Account
+    // 4. System Program
+    // 5. Token Program
+    ExecuteProposalInvestment {},
+
+    // 1. Executor Wallet
+    // 2. Proposal Account
+    // 3. Fund Account
+    // 4. Vault Account
+    // 5. Governance Mint Account
+    // 6. System Program
+    // 7. Token Program
+    // 8. Proposer's Wallet, receives the bond back
+    // 9. Associated Token Program (investment proposals)
+    // 10. [..] Per leg of investment proposals: Vault's From Asset Token Account, Vault's To Asset
+    //    Token Account, To Asset Mint, DEX Program, then for Jupiter legs DEX Event Authority and
+    //    [..] route accounts, for Orca legs Whirlpool, Token Vault A, Token Vault B, Tick Arrays 0-2, Oracle
+    //    or Removed Member's User-specific PDA and Wallet (remove-member proposals)
+    //    or Vault's WSOL Token Account, Recipient's WSOL Token Account and Fund's Reserve WSOL
+    //    Account (withdrawal proposals)
+    //    or Target Member's User-specific PDA (set-roles proposals)
+    // Investment proposals may be executed over several transactions, each with routes and leg
+    // accounts for the next pending legs; the proposal is marked executed once every leg is swapped.
+    Execute {
+        proposal: Pubkey,
+        routes: Vec<SwapRoute>,
+    },
+    LeaveFund{fund_name: String },
+
+    // 1. Proposer Account
+    // 2. Proposer's User-specific PDA
+    // 3. Fund Account
+    // 4. Proposal Account
+    // 5. System Program
+    // 6. Governance Mint Account
+    // 7. Fund's Proposal Registry PDA
+    InitProposalMembership {
+        proposal_kind: u8,
+        target_member: Pubkey,
+        deadline: i64,
+        fund_name: String,
+    },
+
+    // 1. Proposer Account
+    // 2. Proposer's User-specific PDA
+    // 3. Fund Account
+    // 4. Proposal Account
+    // 5. System Program
+    // 6. Governance Mint Account
+    // 7. Fund's Proposal Registry PDA
+    InitProposalWithdrawal {
+        recipient: Pubkey,
+        lamports: u64,
+        deadline: i64,
+        fund_name: String,
+    },
+
+    // 1. Voter Account
+    // 2. Vote Account
+    // 3. Proposal Account
+    // 4. Fund Account
+    // 5. Voter's User-specific PDA
+    ChangeVote {
+        vote: u8,
+        fund_name: String,
+    },
+
+    // 1. Creator's Wallet
+    // 2. Fund Account
+    // 3. Vault Account
+    // 4. Vault's WSOL Token Account
+    // 5. Token Program
+    // 6. Governance Mint Account
+    // 7. Fund's Reserve WSOL Account
+    // 8. [..] Vault's ATA for every accepted mint other than WSOL, in accepted_mints order, all empty
+    // 9. [..] For every member: User-specific PDA and WSOL Token Account
+    CloseFund {
+        fund_name: String,
+    },
+
+    // 1. Proposer Account
+    // 2. Proposer's User-specific PDA
+    // 3. Fund Account
+    // 4. Proposal Account
+    // 5. System Program
+    // 6. Governance Mint Account
+    // 7. Fund's Proposal Registry PDA
+    InitProposalConfigChange {
+        config_change: ConfigChange,
+        deadline: i64,
+        fund_name: String,
+    },
+
+    // 1. Proposal Account
+    // 2. Fund Account
+    GetProposalStatus {
+        fund_name: String,
+    },
+
+    // 1. Member's Wallet
+    // 2. Fund Account
+    // 3. Member's User-specific PDA
+    // 4. Governance Mint Account
+    // 5. Member's Governance Token Account
+    GetMemberPosition {
+        fund_name: String,
+    },
+
+    // 1. Member's Wallet
+    // 2. Member's User-specific PDA
+    // 3. Fund Account
+    // 4. Delegate's User-specific PDA (omitted when clearing the delegation)
+    DelegateVote {
+        delegate: Pubkey,
+        fund_name: String,
+    },
+
+    // Adds lamports to the vault without minting governance tokens, open to anyone
+    // 1. Donor's Wallet
+    // 2. Vault Account
+    // 3. Vault's WSOL Token Account
+    // 4. Fund Account
+    // 5. System Program
+    // 6. Token Program
+    DonateSol {
+        amount: u64,
+        fund_name: String,
+    },
+
+    // Proposer withdraws a proposal nobody has voted on yet
+    // 1. Proposer's Wallet
+    // 2. Proposal Account
+    // 3. Fund Account
+    CancelProposal {
+        fund_name: String,
+    },
+
+    // 1. Voter Account
+    // 2. System Program
+    // 3. Voter's User-specific PDA
+    // 4. Fund Account
+    // 5. [..] Per vote, in order: Proposal Account and the Voter's Vote PDA on it
+    BatchVote {
+        votes: Vec<(Pubkey, u8)>,
+        fund_name: String,
+    },
+
+    // Lists a Fund the wallet is a member of in its global User PDA, for members who joined by
+    // proposal or by depositing
+    // 1. Member's Wallet
+    // 2. Member's User PDA (created if missing)
+    // 3. Fund Account
+    // 4. System Program
+    RegisterFundMembership {
+        fund_name: String,
+    },
+
+    // Closes an empty token account of the Vault, its rent goes to the Vault PDA
+    // 1. Member's Wallet
+    // 2. Fund Account
+    // 3. Vault Account
+    // 4. Vault's Token Account to close
+    // 5. Token Program
+    CloseVaultTokenAccount {
+        fund_name: String,
+    },
+
+    // 1. Creator's Wallet
+    // 2. Fund Account
+    FreezeDeposits {
+        frozen: bool,
+        nonce: u64,
+        fund_name: String,
+    },
+
+    // 1. Fund Account
+    GetMembers {
+        offset: u32,
+        fund_name: String,
+    },
+
+    // 1. Proposer Account, needs ROLE_ADMIN
+    // 2. Proposer's User-specific PDA
+    // 3. Fund Account
+    // 4. Proposal Account
+    // 5. System Program
+    // 6. Governance Mint Account
+    // 7. Fund's Proposal Registry PDA
+    InitProposalSetRoles {
+        target_member: Pubkey,
+        roles: u8,
+        deadline: i64,
+        fund_name: String,
+    },
+
+    // 1. Creator's Wallet
+    // 2. Fund Account
+    // 3. Vault Account
+    // 4. Vault's WSOL Token Account
+    // 5. Fund's Reserve WSOL Account
+    // 6. Pyth SOL/USD Price Account (only when the Fund accepts mints other than WSOL)
+    // 7.. (Vault's Token Account, Mint Account, Pyth Price Account) per accepted mint other than
+    //     WSOL, in the Fund's accepted_mints order
+    RefreshVaultValue {
+        fund_name: String,
+    },
+
+    // 1. Creator's Wallet
+    // 2. New Creator's Wallet, signs to accept
+    // 3. Fund Account
+    TransferCreator {
+        new_creator: Pubkey,
+        nonce: u64,
+        fund_name: String,
+    },
+
+    // 1. Proposal Account
+    // 2. Fund Account
+    // 3. [..] Per pending leg: DEX Program, then for Orca legs Whirlpool, Token Vault A,
+    //    Token Vault B, Tick Arrays 0-2, Oracle
+    // Read-only: returns the expected output of each leg as a Borsh Vec<u64>
+    SimulateExecute {
+        proposal: Pubkey,
+        routes: Vec<SwapRoute>,
+    },
+
+    // 1. Fund Account
+    // 2. Fund's Proposal Registry PDA
+    GetOpenProposals {
+        offset: u32,
+        fund_name: String,
+    },
+
+    // 1. Payer's Wallet, covers the growth of every migrated account
+    // 2. Fund Account
+    // 3. System Program
+    // 4. Vault Account
+    // 5. [..] (Member's Wallet, Member's User-specific PDA) per active member of the version 1 Fund
+    MigrateFund {
+        fund_name: String,
+    },
+
+    // 1. Fund Account
+    // 2. Vault Account
+    // 3. Vault's WSOL Token Account
+    // 4. Governance Mint Account
+    // 5. Fund's Reserve WSOL Account
+    // 6. Pyth SOL/USD Price Account (only when the Fund accepts mints other than WSOL)
+    // 7.. (Vault's Token Account, Mint Account, Pyth Price Account) per accepted mint other than
+    //     WSOL, in the Fund's accepted_mints order
+    GetFundValuation {
+        fund_name: String,
+    },
+
+    // 1. Creator's Wallet
+    // 2. Fund Account
+    SetPaused {
+        paused: bool,
+        nonce: u64,
+        fund_name: String,
+    },
+
+    // 1. Closer's Wallet (Proposer or Fund Creator)
+    // 2. Proposal Account
+    // 3. Fund Account
+    // 4. Proposer's Wallet
+    // 5. Vault Account
+    // 6. Fund's Proposal Registry PDA
+    // 7. Proposer's User-specific PDA
+    // 8. Vault's WSOL Token Account
+    // 9. Token Program
+    CloseProposal {
+        fund_name: String,
+    },
+
+    // 1. Fund Account
+    // 2. Governance Mint Account
+    GetFundSummary {
+        fund_name: String,
+    },
+
+    // 1. Member's Wallet
+    // 2. Member's WSOL Token Account
+    // 3. Vault Account
+    // 4. Vault's WSOL Token Account
+    // 5. Fund Account
+    // 6. User-specific PDA
+    // 7. Governance Mint Account
+    // 8. Member's Governance Token Account
+    // 9. Token Program
+    // 10. Fund's Reserve WSOL Account, withdrawals are paid from it first
+    WithdrawSol {
+        amount: u64,
+        fund_name: String,
+    },
+}
+
+impl FundInstruction {
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (tag, rest) = input
+            .split_first()
+            .ok_or(FundError::InstructionUnpackError)?;
+
+        Ok(match tag {
+            0 => {
+                let (privacy, rest) = Self::unpack_members(rest)?;
+                let (name, rest) = Self::unpack_name(rest)?;
+                let (config, rest) = Self::unpack_config(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitFundAccount {
+                    privacy,
+                    name,
+                    config,
+                    fund_name,
+                }
+            }
+            1 => {
+                let (amount, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitDepositSol {
+                    amount,
+                    fund_name,
+                }
+            } 
+            2 => {
+                let (proposal_kind, rest) = Self::unpack_proposal_kind(rest)?;
+                let (&num_of_swaps, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
+                if num_of_swaps == 0 || num_of_swaps > MAX_PROPOSAL_LEGS {
+                    return Err(FundError::InstructionUnpackError.into());
+                }
+                let (amounts, rest) = Self::unpack_amounts(rest, num_of_swaps)?;
+                let (min_out, rest) = Self::unpack_amounts(rest, num_of_swaps)?;
+                let (dex_tags, rest) = Self::unpack_dex_tags(rest, num_of_swaps)?;
+                let (deadline, rest) = Self::unpack_deadline(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+
+                Self::InitProposalInvestment {
+                    proposal_kind,
+                    amounts,
+                    min_out,
+                    dex_tags,
+                    deadline,
+                    fund_name,
+                }
+            }
+            3 => {
+                let (vote, rest) = Self::unpack_vote(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::Vote {
+                    vote,
+                    fund_name,
+                }
+            }
+            4 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::AddFundMember { fund_name }
+            }
+            5 => {
+                Self::ExecuteProposalInvestment {}
+            }
+            // 6 was InitRentAccount, every account is now paid for by the signer creating it
+            7 => {
+                let user_seed = Self::unpack_last_seed(rest)?;
+                Self::InitUserAccount { user_seed }
+            }
+            8 => {
+                let (amount, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitDepositToken {
+                    amount,
+                    fund_name,
+                }
+            }
+            9 => {
+                Self::DeleteFund { }
+            }
+            10 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+
+                Self::LeaveFund { fund_name }
+            }
+            11 => {
+                let (proposal, rest) = Self::unpack_pubkey(rest)?;
+                let routes = if rest.is_empty() {
+                    vec![]
+                } else {
+                    Vec::<SwapRoute>::try_from_slice(rest).map_err(|_| FundError::InstructionUnpackError)?
+                };
+                Self::Execute {
+                    proposal,
+                    routes,
+                }
+            }
+            12 => {
+                let (amount, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::WithdrawSol {
+                    amount,
+                    fund_name,
+                }
+            }
+            13 => {
+                let (proposal_kind, rest) = Self::unpack_membership_kind(rest)?;
+                let (target_member, rest) = Self::unpack_pubkey(rest)?;
+                let (deadline, rest) = Self::unpack_deadline(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitProposalMembership {
+                    proposal_kind,
+                    target_member,
+                    deadline,
+                    fund_name,
+                }
+            }
+            14 => {
+                let (&paused, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
+                let (nonce, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::SetPaused {
+                    paused: paused != 0,
+                    nonce,
+                    fund_name,
+                }
+            }
+            15 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::CloseProposal { fund_name }
+            }
+            16 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetFundSummary { fund_name }
+            }
+            17 => {
+                let (recipient, rest) = Self::unpack_pubkey(rest)?;
+                let (lamports, rest) = Self::unpack_amount(rest)?;
+                let (deadline, rest) = Self::unpack_deadline(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitProposalWithdrawal {
+                    recipient,
+                    lamports,
+                    deadline,
+                    fund_name,
+                }
+            }
+            18 => {
+                let (vote, rest) = Self::unpack_vote(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::ChangeVote {
+                    vote,
+                    fund_name,
+                }
+            }
+            19 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::CloseFund { fund_name }
+            }
+            20 => {
+                let (config_change, rest) = Self::unpack_config_change(rest)?;
+                let (deadline, rest) = Self::unpack_deadline(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitProposalConfigChange {
+                    config_change,
+                    deadline,
+                    fund_name,
+                }
+            }
+            21 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetProposalStatus { fund_name }
+            }
+            22 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetMemberPosition { fund_name }
+            }
+            23 => {
+                let (delegate, rest) = Self::unpack_pubkey(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::DelegateVote { delegate, fund_name }
+            }
+            24 => {
+                let (amount, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::DonateSol { amount, fund_name }
+            }
+            25 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::CancelProposal { fund_name }
+            }
+            26 => {
+                let (&num_votes, mut rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
+                if num_votes == 0 || num_votes > MAX_BATCH_VOTES {
+                    return Err(FundError::InstructionUnpackError.into());
+                }
+                let mut votes = Vec::with_capacity(num_votes as usize);
+                for _ in 0..num_votes {
+                    let (proposal, tail) = Self::unpack_pubkey(rest)?;
+                    let (vote, tail) = Self::unpack_vote(tail)?;
+                    votes.push((proposal, vote));
+                    rest = tail;
+                }
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::BatchVote { votes, fund_name }
+            }
+            27 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::RegisterFundMembership { fund_name }
+            }
+            28 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::CloseVaultTokenAccount { fund_name }
+            }
+            29 => {
+                let (&frozen, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
+                let (nonce, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::FreezeDeposits {
+                    frozen: frozen != 0,
+                    nonce,
+                    fund_name,
+                }
+            }
+            30 => {
+                let (offset, rest) = Self::unpack_u32(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetMembers { offset, fund_name }
+            }
+            31 => {
+                let (target_member, rest) = Self::unpack_pubkey(rest)?;
+                let (&roles, rest) = rest.split_first().ok_or(FundError::InstructionUnpackError)?;
+                let (deadline, rest) = Self::unpack_deadline(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::InitProposalSetRoles {
+                    target_member,
+                    roles,
+                    deadline,
+                    fund_name,
+                }
+            }
+            32 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::RefreshVaultValue { fund_name }
+            }
+            33 => {
+                let (new_creator, rest) = Self::unpack_pubkey(rest)?;
+                let (nonce, rest) = Self::unpack_amount(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::TransferCreator { new_creator, nonce, fund_name }
+            }
+            34 => {
+                let (proposal, rest) = Self::unpack_pubkey(rest)?;
+                let routes = Vec::<SwapRoute>::try_from_slice(rest).map_err(|_| FundError::InstructionUnpackError)?;
+                Self::SimulateExecute {
+                    proposal,
+                    routes,
+                }
+            }
+            35 => {
+                let (offset, rest) = Self::unpack_u32(rest)?;
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetOpenProposals { offset, fund_name }
+            }
+            36 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::MigrateFund { fund_name }
+            }
+            37 => {
+                let fund_name = Self::unpack_last_seed(rest)?;
+                Self::GetFundValuation { fund_name }
+            }
+            _ => {
+                return Err(FundError::InstructionUnpackError.into());
+            }
+        })
+
+    }
+
+    fn unpack_proposal_kind(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (&proposal_kind, rest) = input
+            .split_first()
+            .ok_or(FundError::InstructionUnpackError)?;
+
+        match proposal_kind {
+            PROPOSAL_KIND_INVESTMENT |
+            PROPOSAL_KIND_ADD_MEMBER |
+            PROPOSAL_KIND_REMOVE_MEMBER |
+            PROPOSAL_KIND_WITHDRAWAL |
+            PROPOSAL_KIND_CONFIG_CHANGE => Ok((proposal_kind, rest)),
+            _ => Err(FundError::InstructionUnpackError.into()),
+        }
+    }
+
+    // Membership proposals only ever change who is in the fund or what they may do
+    fn unpack_membership_kind(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (&proposal_kind, rest) = input
+            .split_first()
+            .ok_or(FundError::InstructionUnpackError)?;
+
+        match proposal_kind {
+            PROPOSAL_KIND_ADD_MEMBER |
+            PROPOSAL_KIND_REMOVE_MEMBER |
+            PROPOSAL_KIND_SET_ROLES => Ok((proposal_kind, rest)),
+            _ => Err(FundError::InstructionUnpackError.into()),
+        }
+    }
+
+    // Vote is 1 for yes and 0 for no, anything else is rejected rather than counted as no
+    fn unpack_vote(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (&vote, rest) = input
+            .split_first()
+            .ok_or(FundError::InstructionUnpackError)?;
+
+        match vote {
+            0 | 1 => Ok((vote, rest)),
+            _ => Err(FundError::InvalidInstruction.into()),
+        }
+    }
+
+    fn unpack_members(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        let (&num, rest) = input
+            .split_first()
+            .ok_or(FundError::InstructionUnpackError)?;
+
+        Ok((num, rest))
+    }
+
+    // Seed is a 1-byte length followed by that many bytes of UTF-8, at most a PDA seed's maximum
+    // length. Every Fund name in instruction data is encoded this way.
+    fn unpack_seed(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+        let (&seed_len, rest) = input.split_first().ok_or(FundError::InstructionUnpackError)?;
+        let seed_len = seed_len as usize;
+        if seed_len == 0 || seed_len > MAX_SEED_LEN || rest.len() < seed_len {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+
+        let (seed, rest) = rest.split_at(seed_len);
+        let seed = std::str::from_utf8(seed).map_err(|_| FundError::InstructionUnpackError)?;
+
+        Ok((seed.to_string(), rest))
+    }
+
+    // Seed that ends the instruction data, anything after it is malformed input
+    fn unpack_last_seed(input: &[u8]) -> Result<String, ProgramError> {
+        let (seed, rest) = Self::unpack_seed(input)?;
+        if !rest.is_empty() {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+
+        Ok(seed)
+    }
+
+    // Display name of the fund, right-padded with zeros by the client
+    fn unpack_name(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+        if input.len() < 32 {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+        let (name_bytes, rest) = input.split_at(32);
+        let name: [u8; 32] = name_bytes.try_into().map_err(|_| FundError::InstructionUnpackError)?;
+
+        Ok((name, rest))
+    }
+
+    // Fund configuration is a fixed-size Borsh struct placed before the fund name
+    fn unpack_config(input: &[u8]) -> Result<(FundConfig, &[u8]), ProgramError> {
+        let mut input_slice = input;
+        let config = FundConfig::deserialize(&mut input_slice).map_err(|_| FundError::InstructionUnpackError)?;
+
+        Ok((config, input_slice))
+    }
+
+    // New Fund settings: name, privacy byte, quorum and pass threshold, Borsh-encoded
+    fn unpack_config_change(input: &[u8]) -> Result<(ConfigChange, &[u8]), ProgramError> {
+        let mut input_slice = input;
+        let config_change = ConfigChange::deserialize(&mut input_slice).map_err(|_| FundError::InstructionUnpackError)?;
+
+        Ok((config_change, input_slice))
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        if input.len() < PUBKEY_BYTES {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+        let (key_bytes, rest) = input.split_at(PUBKEY_BYTES);
+        let key = Pubkey::try_from(key_bytes).map_err(|_| FundError::InstructionUnpackError)?;
+
+        Ok((key, rest))
+    }
+
+    fn unpack_amount(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < BYTE_SIZE_8 {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+        let (amount_bytes, rest) = input.split_at(BYTE_SIZE_8);
+
+        let amount = u64::from_le_bytes(amount_bytes.try_into().expect("Invalid amount length"));
+
+        Ok((amount, rest))
+    }
+
+    fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+        let (value_bytes, rest) = input.split_at(4);
+        let value = u32::from_le_bytes(value_bytes.try_into().expect("Invalid u32 length"));
+
+        Ok((value, rest))
+    }
+
+    fn unpack_amounts(input: &[u8], num_of_swaps: u8) -> Result<(Vec<u64>, &[u8]), ProgramError> {
+        if input.len() < BYTE_SIZE_8*(num_of_swaps as usize) {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+
+        let mut amounts: Vec<u64> = Vec::new();
+        let mut input_slice = input;
+        for _i in 0..num_of_swaps {
+            let (amount, rest) = Self::unpack_amount(input_slice)?;
+            amounts.push(amount);
+            input_slice = rest;
+        }
+
+        Ok((amounts, input_slice))
+    }
+
+    fn unpack_dex_tags(input: &[u8], num_of_swaps: u8) -> Result<(Vec<u8>, &[u8]), ProgramError> {
+        if input.len() < num_of_swaps as usize {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+
+        let mut dex_tags: Vec<u8> = Vec::new();
+        let mut input_slice = input;
+        for _i in 0..num_of_swaps {
+            let (dex_tag, rest) = input_slice.split_first().ok_or(FundError::InstructionUnpackError)?;
+            dex_tags.push(*dex_tag);
+            input_slice = rest;
+        }
+
+        Ok((dex_tags, input_slice))
+    }
+
+    fn unpack_deadline(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+        if input.len() < BYTE_SIZE_8 {
+            return Err(FundError::InstructionUnpackError.into());
+        }
+
+        let (deadline_data, rest) = input.split_at(BYTE_SIZE_8);
+        let deadline = i64::from_le_bytes(deadline_data.try_into().expect("Failed to get Deadline"));
+        Ok((deadline, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(len: usize) -> Vec<u8> {
+        let mut input = vec![len as u8];
+        input.resize(len + 1, b'a');
+        input
+    }
+
+    #[test]
+    fn unpack_seed_rejects_empty_seed() {
+        assert!(FundInstruction::unpack_seed(&[]).is_err());
+        assert!(FundInstruction::unpack_seed(&seed(0)).is_err());
+    }
+
+    #[test]
+    fn unpack_seed_rejects_short_input() {
+        // Length prefix promises more bytes than follow it
+        let mut input = seed(8);
+        input.truncate(5);
+        assert_eq!(FundInstruction::unpack_seed(&input).err(), Some(FundError::InstructionUnpackError.into()));
+    }
+
+    #[test]
+    fn unpack_seed_reads_short_and_exact_seeds() {
+        let input = seed(1);
+        let (name, rest) = FundInstruction::unpack_seed(&input).unwrap();
+        assert_eq!(name, "a");
+        assert!(rest.is_empty());
+
+        let mut input = seed(MAX_SEED_LEN);
+        input.push(7);
+        let (name, rest) = FundInstruction::unpack_seed(&input).unwrap();
+        assert_eq!(name.len(), MAX_SEED_LEN);
+        assert_eq!(rest, &[7]);
+    }
+
+    #[test]
+    fn unpack_seed_rejects_over_length_seed() {
+        assert!(FundInstruction::unpack_seed(&seed(MAX_SEED_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn unpack_seed_rejects_invalid_utf8() {
+        assert!(FundInstruction::unpack_seed(&[2, 0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn fund_name_must_end_the_instruction() {
+        let mut input = vec![4];
+        input.extend(seed(5));
+        assert!(matches!(FundInstruction::unpack(&input), Ok(FundInstruction::AddFundMember { fund_name }) if fund_name == "aaaaa"));
+
+        input.push(0);
+        assert!(FundInstruction::unpack(&input).is_err());
+    }
+
+    fn investment_proposal(num_legs: u8) -> Vec<u8> {
+        let mut input = vec![2, PROPOSAL_KIND_INVESTMENT, num_legs];
+        for _ in 0..2 * num_legs as usize {
+            input.extend_from_slice(&1_u64.to_le_bytes()); // amounts, then min_out
+        }
+        input.extend(std::iter::repeat_n(0, num_legs as usize)); // dex_tags
+        input.extend_from_slice(&0_i64.to_le_bytes()); // deadline
+        input.extend(seed(5));
+        input
+    }
+
+    #[test]
+    fn investment_proposal_legs_are_capped() {
+        let input = investment_proposal(MAX_PROPOSAL_LEGS);
+        assert!(matches!(
+            FundInstruction::unpack(&input),
+            Ok(FundInstruction::InitProposalInvestment { amounts, .. }) if amounts.len() == MAX_PROPOSAL_LEGS as usize
+        ));
+
+        let input = investment_proposal(MAX_PROPOSAL_LEGS + 1);
+        assert_eq!(FundInstruction::unpack(&input).err(), Some(FundError::InstructionUnpackError.into()));
+        assert!(FundInstruction::unpack(&investment_proposal(0)).is_err());
+    }
+
+    #[test]
+    fn vote_is_read_after_the_tag() {
+        for vote in [0, 1] {
+            let mut input = vec![3, vote];
+            input.extend(seed(5));
+            assert!(matches!(FundInstruction::unpack(&input), Ok(FundInstruction::Vote { vote: unpacked, .. }) if unpacked == vote));
+        }
+        let mut input = vec![3, 3];
+        input.extend(seed(5));
+        assert_eq!(FundInstruction::unpack(&input).err(), Some(FundError::InvalidInstruction.into()));
+    }
+
+    fn membership_proposal(proposal_kind: u8) -> Vec<u8> {
+        let mut input = vec![13, proposal_kind];
+        input.extend_from_slice(Pubkey::new_unique().as_ref());
+        input.extend_from_slice(&1_000i64.to_le_bytes());
+        input.extend(seed(5));
+        input
+    }
+
+    #[test]
+    fn membership_proposals_only_take_membership_kinds() {
+        for kind in [PROPOSAL_KIND_ADD_MEMBER, PROPOSAL_KIND_REMOVE_MEMBER, PROPOSAL_KIND_SET_ROLES] {
+            assert!(matches!(
+                FundInstruction::unpack(&membership_proposal(kind)),
+                Ok(FundInstruction::InitProposalMembership { proposal_kind, .. }) if proposal_kind == kind
+            ));
+        }
+        for kind in [PROPOSAL_KIND_INVESTMENT, PROPOSAL_KIND_WITHDRAWAL, PROPOSAL_KIND_CONFIG_CHANGE, 42] {
+            assert_eq!(
+                FundInstruction::unpack(&membership_proposal(kind)).err(),
+                Some(FundError::InstructionUnpackError.into())
+            );
+        }
+    }
+}
@@ -11,5 +11,9 @@ pub mod processor;
 pub mod state;
 pub mod errors;
 pub mod utils;
+pub mod dex;
+pub mod views;
+pub mod events;
+pub mod oracle;
 
 entrypoint!(process_instruction);
@@ -0,0 +1,175 @@
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use crate::errors::FundError;
+
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+pub const SOL_USD_PRICE_FEED: Pubkey = pubkey!("H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG");
+
+// Oldest Pyth aggregate a valuation accepts, in seconds, when the Fund's creator doesn't set
+// max_price_age, and the most a creator may set it to
+pub const DEFAULT_MAX_PRICE_AGE: i64 = 60;
+pub const MAX_PRICE_AGE: i64 = 60 * 60;
+
+// Widest confidence interval accepted, relative to the price, 2%
+pub const MAX_CONFIDENCE_BPS: u64 = 200;
+
+// Exponent every price returned by read_price is scaled to, the one Pyth uses for USD feeds
+pub const PRICE_EXPO: i32 = -8;
+
+// Pyth v2 price account layout
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPO_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], ProgramError> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| <[u8; N]>::try_from(bytes).ok())
+        .ok_or_else(|| FundError::InvalidOracleAccount.into())
+}
+
+// Aggregate price of a Pyth price account scaled to PRICE_EXPO. Rejects feeds that aren't
+// trading, are older than `max_age` seconds or whose confidence is wider than MAX_CONFIDENCE_BPS.
+pub fn read_price(account: &AccountInfo, max_age: i64) -> Result<i64, ProgramError> {
+    if *account.owner != PYTH_PROGRAM_ID {
+        return Err(FundError::InvalidOracleAccount.into());
+    }
+    parse_price(&account.data.borrow(), Clock::get()?.unix_timestamp, max_age)
+}
+
+// read_price on a price account's data, checked against `now`
+fn parse_price(data: &[u8], now: i64, max_age: i64) -> Result<i64, ProgramError> {
+    if u32::from_le_bytes(read_bytes(data, MAGIC_OFFSET)?) != PYTH_MAGIC ||
+       u32::from_le_bytes(read_bytes(data, VERSION_OFFSET)?) != PYTH_VERSION ||
+       u32::from_le_bytes(read_bytes(data, ACCOUNT_TYPE_OFFSET)?) != PYTH_ACCOUNT_TYPE_PRICE ||
+       u32::from_le_bytes(read_bytes(data, AGG_STATUS_OFFSET)?) != PYTH_STATUS_TRADING {
+        return Err(FundError::InvalidOracleAccount.into());
+    }
+
+    let timestamp = i64::from_le_bytes(read_bytes(data, TIMESTAMP_OFFSET)?);
+    if now.saturating_sub(timestamp) > max_age {
+        return Err(FundError::StaleOraclePrice.into());
+    }
+
+    let price = i64::from_le_bytes(read_bytes(data, AGG_PRICE_OFFSET)?);
+    let conf = u64::from_le_bytes(read_bytes(data, AGG_CONF_OFFSET)?);
+    if price <= 0 || (conf as u128) * 10000 > (price as u128) * MAX_CONFIDENCE_BPS as u128 {
+        return Err(FundError::InvalidOracleAccount.into());
+    }
+
+    let expo = i32::from_le_bytes(read_bytes(data, EXPO_OFFSET)?);
+    let scaled = if expo >= PRICE_EXPO {
+        10i64.checked_pow(expo.abs_diff(PRICE_EXPO)).and_then(|factor| price.checked_mul(factor))
+    } else {
+        10i64.checked_pow(expo.abs_diff(PRICE_EXPO)).map(|factor| price / factor)
+    };
+    match scaled {
+        Some(price) if price > 0 => Ok(price),
+        _ => Err(FundError::InvalidOracleAccount.into()),
+    }
+}
+
+// Lamport value of `amount` base units of a token with `decimals`, given the token's and SOL's
+// prices as returned by read_price
+pub fn token_value_in_lamports(amount: u64, decimals: u8, token_price: i64, sol_price: i64) -> Result<u64, ProgramError> {
+    let numerator = (amount as u128)
+        .checked_mul(token_price as u128)
+        .and_then(|value| value.checked_mul(10u128.pow(9)))
+        .ok_or(FundError::ArithmeticOverflow)?;
+    let denominator = (sol_price as u128)
+        .checked_mul(10u128.checked_pow(decimals as u32).ok_or(FundError::ArithmeticOverflow)?)
+        .ok_or(FundError::ArithmeticOverflow)?;
+    u64::try_from(numerator / denominator).map_err(|_| FundError::ArithmeticOverflow.into())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const PRICE_ACCOUNT_LEN: usize = 240;
+
+    // Trading Pyth v2 price account published at `timestamp`
+    pub(crate) fn price_account(price: i64, conf: u64, expo: i32, timestamp: i64) -> Vec<u8> {
+        let mut data = vec![0; PRICE_ACCOUNT_LEN];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4].copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[EXPO_OFFSET..EXPO_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&timestamp.to_le_bytes());
+        data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn reads_a_fresh_price_scaled_to_price_expo() {
+        // $150.00000000 published 10s ago
+        let data = price_account(15_000_000_000, 1_000_000, -8, 990);
+        assert_eq!(parse_price(&data, 1_000, 60), Ok(15_000_000_000));
+
+        // Same price at a coarser and a finer exponent
+        assert_eq!(parse_price(&price_account(150_000, 10, -3, 990), 1_000, 60), Ok(15_000_000_000));
+        assert_eq!(parse_price(&price_account(1_500_000_000_000, 100, -10, 990), 1_000, 60), Ok(15_000_000_000));
+    }
+
+    #[test]
+    fn rejects_a_price_older_than_max_age() {
+        let data = price_account(15_000_000_000, 1_000_000, -8, 900);
+        assert_eq!(parse_price(&data, 1_000, 100), Ok(15_000_000_000));
+        assert_eq!(parse_price(&data, 1_000, 99), Err(FundError::StaleOraclePrice.into()));
+    }
+
+    #[test]
+    fn rejects_a_wide_confidence_or_non_positive_price() {
+        // 2% of the price is the widest interval accepted
+        assert!(parse_price(&price_account(10_000, 200, -8, 1_000), 1_000, 60).is_ok());
+        assert_eq!(parse_price(&price_account(10_000, 201, -8, 1_000), 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+        assert_eq!(parse_price(&price_account(0, 0, -8, 1_000), 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+        assert_eq!(parse_price(&price_account(-5, 0, -8, 1_000), 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+    }
+
+    #[test]
+    fn rejects_accounts_that_arent_trading_price_accounts() {
+        let mut data = price_account(10_000, 0, -8, 1_000);
+        data[AGG_STATUS_OFFSET] = 0;
+        assert_eq!(parse_price(&data, 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+
+        let mut data = price_account(10_000, 0, -8, 1_000);
+        data[MAGIC_OFFSET] ^= 1;
+        assert_eq!(parse_price(&data, 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+
+        let data = price_account(10_000, 0, -8, 1_000);
+        assert_eq!(parse_price(&data[..AGG_PRICE_OFFSET], 1_000, 60), Err(FundError::InvalidOracleAccount.into()));
+    }
+
+    #[test]
+    fn rejects_accounts_not_owned_by_pyth() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = price_account(10_000, 0, -8, 1_000);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(read_price(&account, 60), Err(FundError::InvalidOracleAccount.into()));
+    }
+
+    #[test]
+    fn values_tokens_in_lamports() {
+        // 2 tokens with 6 decimals at $75 are worth 1 SOL at $150
+        assert_eq!(token_value_in_lamports(2_000_000, 6, 7_500_000_000, 15_000_000_000), Ok(1_000_000_000));
+    }
+}
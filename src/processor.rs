@@ -1,1208 +1,6019 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program:: pubkey;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_pack::Pack, pubkey:: Pubkey, system_instruction, sysvar::{rent::Rent, Sysvar}
-    // instruction::{Instruction},
-};
-use spl_token::state::Account as TokenAccount;
-use spl_associated_token_account::instruction::create_associated_token_account;
-use crate::{
-    errors::FundError,
-    instruction::FundInstruction,
-    state::{FundAccount, InvestmentProposalAccount, UserAccount, UserSpecificAccount, VaultAccount, VoteAccount}
-};
-use mpl_token_metadata::types::DataV2;
-use mpl_token_metadata::instructions::{CreateMetadataAccountV3, CreateMetadataAccountV3InstructionArgs};
-
-pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
-
-pub fn process_instruction<'a>(
-    program_id: &Pubkey,
-    accounts: &'a[AccountInfo<'a>],
-    data: &[u8]
-) -> ProgramResult {
-    let instruction = FundInstruction::unpack(data)?;
-    match instruction {
-
-        FundInstruction::InitFundAccount { privacy, fund_name} => {
-            msg!("Instruction: Init Fund Account");
-            process_init_fund_account(program_id, accounts, fund_name, privacy)
-        }
-
-        FundInstruction::InitUserAccount {  } => {
-            msg!("Instruction: Init User Account");
-            process_init_user_account(program_id, accounts)
-        }
-
-        FundInstruction::AddFundMember { fund_name } => {
-            msg!("Instruction: Add Fund Member");
-            process_add_member(program_id, accounts, fund_name)
-        }
-
-        // FundInstruction::InitDepositSol { amount , fund_name} => {
-        //     msg!("Instruction: Init Deposit");
-        //     process_init_deposit_sol(program_id, accounts, amount, fund_name)
-        // }
-
-        FundInstruction::InitDepositToken { amount, fund_name } => {
-            msg!("Instruction: Init Deposit Token");
-            process_init_deposit_token(program_id, accounts, amount, fund_name)
-        }
-
-        FundInstruction::InitProposalInvestment { 
-            amounts,
-            // dex_tags,
-            deadline,
-            fund_name,
-        } => {
-            msg!("Instruction: Init Proposal");
-            process_init_investment_proposal(program_id, accounts, amounts, deadline, fund_name)
-        }
-
-        FundInstruction::Vote {vote, fund_name} => {
-            msg!("Instruction: Voting on Proposal");
-            process_vote_on_proposal(program_id, accounts, vote, fund_name)
-        }
-
-        FundInstruction::InitRentAccount {  } => {
-            msg!("Instruction: Init Rent Account");
-            process_init_rent_account(program_id, accounts)
-        }
-
-        FundInstruction::LeaveFund { fund_name } => {
-            msg!("Instruction: Leave Fund");
-            process_leave_fund(program_id, fund_name, accounts)
-        }
-        // FundInstruction::DeleteFund {  } => {
-        //     msg!("Instruction: Delete Fund");
-        //     process_delete_fund(program_id, accounts)
-        // }
-
-        _ => Err(FundError::InvalidInstruction.into()),
-    }
-}
-
-fn process_init_fund_account<'a>(
-    program_id: &Pubkey,
-    accounts: &'a [AccountInfo<'a>],
-    fund_name: String,
-    privacy: u8,
-) -> ProgramResult {
-    let current_time = Clock::get()?.unix_timestamp;
-
-    let accounts_iter = &mut accounts.iter();
-    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint
-    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA Account
-    let system_program_info = next_account_info(accounts_iter)?; // System Program
-    let token_program_info = next_account_info(accounts_iter)?; // Token Program (2020)
-    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA Account
-    let creator_wallet_info = next_account_info(accounts_iter)?; // Creator Wallet Address
-    let metadata_account_info = next_account_info(accounts_iter)?; // Metadat PDA for Governance Mint
-    let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar
-    let token_metadata_program_info = next_account_info(accounts_iter)?; // Token Metadata Program
-    let user_account_info = next_account_info(accounts_iter)?; // Global User Account
-    let user_specific_info = next_account_info(accounts_iter)?;
-
-    // Creator should be signer
-    if !creator_wallet_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    // Deriving required PDAs
-    let (fund_pda, fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
-    let (governance_mint, governance_bump) = Pubkey::find_program_address(&[b"governance", fund_pda.as_ref()], program_id);
-    let (user_pda, _user_bump) = Pubkey::find_program_address(&[b"user", creator_wallet_info.key.as_ref()], program_id);
-
-    // Check if any of the provided PDA differes from the derived
-    if *fund_account_info.key != fund_pda ||
-       *vault_account_info.key != vault_pda ||
-       *governance_mint_info.key != governance_mint ||
-       *user_account_info.key != user_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // Check if the an account already exists on that PDA
-    if fund_account_info.lamports() > 0 {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // Calculate Rent
-    let rent = &Rent::from_account_info(rent_sysvar_info)?;
-    let fund_space = 154 as usize;
-    let vault_space = 40 as usize;
-    let mint_space = spl_token::state::Mint::LEN;
-
-    // Creating the Fund Account PDA
-    invoke_signed(
-        &system_instruction::create_account(
-            creator_wallet_info.key,
-            fund_account_info.key,
-            rent.minimum_balance(fund_space),
-            fund_space as u64,
-            program_id,
-        ),
-        &[creator_wallet_info.clone(), fund_account_info.clone(), system_program_info.clone()],
-        &[&[b"fund", fund_name.as_bytes(), &[fund_bump]]]
-    )?;
-
-    // Creating the Vault Account PDA
-    invoke_signed(
-        &system_instruction::create_account(
-            creator_wallet_info.key,
-            vault_account_info.key,
-            rent.minimum_balance(vault_space),
-            vault_space as u64,
-            program_id,
-        ),
-        &[creator_wallet_info.clone(), vault_account_info.clone(), system_program_info.clone()],
-        &[&[b"vault", fund_pda.as_ref(), &[vault_bump]]],
-    )?;
-
-    // Creating the Governance Mint account
-    invoke_signed(
-        &system_instruction::create_account(
-            creator_wallet_info.key,
-            governance_mint_info.key,
-            rent.minimum_balance(mint_space),
-            mint_space as u64,
-            token_program_info.key,
-        ),
-        &[creator_wallet_info.clone(), governance_mint_info.clone(), system_program_info.clone()],
-        &[&[b"governance", fund_pda.as_ref(), &[governance_bump]]],
-    )?;
-    invoke_signed(
-        &spl_token::instruction::initialize_mint(
-            token_program_info.key,
-            governance_mint_info.key,
-            fund_account_info.key,
-            None,
-            0,
-        )?,
-        &[governance_mint_info.clone(), token_program_info.clone(), rent_sysvar_info.clone()],
-        &[&[b"governance", fund_pda.as_ref(), &[governance_bump]]],
-    )?;
-
-    // Deriving PDA to store Mint metadata
-    let (metadata_pda, _bump) = Pubkey::find_program_address(
-        &[
-            b"metadata",
-            TOKEN_METADATA_PROGRAM_ID.as_ref(),
-            governance_mint_info.key.as_ref(),
-        ],
-        &TOKEN_METADATA_PROGRAM_ID,
-    );
-
-    // Token metadata
-    let token_name = fund_name.clone();
-    let token_symbol = String::from("GOV");
-    let token_uri = "".to_string();
-
-    // Instruction to create Mtadata account at derived PDA
-    let create_metadata_ix = CreateMetadataAccountV3 {
-        metadata: metadata_pda,
-        mint: *governance_mint_info.key,
-        mint_authority: *fund_account_info.key,
-        payer: *creator_wallet_info.key,
-        update_authority: (*fund_account_info.key, true),
-        system_program: *system_program_info.key,
-        rent: None,
-    }.instruction(CreateMetadataAccountV3InstructionArgs {
-        data: DataV2 {
-            name: token_name,
-            symbol: token_symbol,
-            uri: token_uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        },
-        is_mutable: true,
-        collection_details: None,
-    });
-
-    // Invoking instruction to create Metadata PDA account
-    invoke_signed(
-        &create_metadata_ix,
-        &[
-            metadata_account_info.clone(),
-            governance_mint_info.clone(),
-            fund_account_info.clone(),
-            fund_account_info.clone(),
-            creator_wallet_info.clone(),
-            system_program_info.clone(),
-            rent_sysvar_info.clone(),
-            token_metadata_program_info.clone(),
-        ],
-        &[&[b"fund", fund_name.as_bytes(), &[fund_bump]]]
-    )?;
-
-    // Converting the fund_name to an array of u8 of fixed size 32
-    let bytes = fund_name.as_bytes();
-    let mut array = [0u8; 32];
-    let len = bytes.len().min(32);
-    array[..len].copy_from_slice(&bytes[..len]);
-
-    // Deserialization and Serialization of Fund data
-    let fund_data = FundAccount {
-        name: array,
-        creator: *creator_wallet_info.key,
-        members: 1 as u64,
-        total_deposit: 0 as u64,
-        governance_mint: *governance_mint_info.key,
-        vault: *vault_account_info.key,
-        is_initialized: true,
-        created_at: current_time,
-        is_private: privacy,
-    };
-    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
-
-    // Deserializing the User Global PDA
-    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
-
-    // Check is User is already in the Fund (vague since fund account is just created)
-    if user_data.funds.contains(fund_account_info.key) {
-        msg!("User is already a member");
-        return Ok(())
-    }
-
-    // Calculate current size and new size of User PDA
-    let current_size = user_account_info.data_len();
-    let new_size = current_size + 32;
-
-    // Calculate current min rent-exempt and new rent-exempt
-    let new_min_balance = rent.minimum_balance(new_size);
-    let current_balance = user_account_info.lamports();
-
-    // Deposit lamports if required
-    if new_min_balance > current_balance {
-        invoke(
-            &system_instruction::transfer(
-                creator_wallet_info.key,
-                user_account_info.key,
-                new_min_balance - current_balance,
-            ),
-            &[creator_wallet_info.clone(), user_account_info.clone(), system_program_info.clone()],
-        )?;
-    }
-
-    // Reallocation for new bytes
-    user_account_info.realloc(new_size, false)?;
-    // Add the new fund address to user funds
-    user_data.funds.push(*fund_account_info.key);
-    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
-
-    // Deserialization and Serialization of Vault Account Data
-    let vault_data = VaultAccount {
-        fund: *fund_account_info.key,
-        last_deposit_time: 0,
-    };
-    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
-
-    msg!("Fund Initialization successful");
-
-    create_user_specific_pda(
-        program_id,
-        creator_wallet_info,
-        system_program_info,
-        fund_account_info,
-        user_specific_info
-    )
-}
-
-fn process_init_user_account(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo]
-) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let creator_account_info = next_account_info(accounts_iter)?; // User Wallet
-    let user_account_info = next_account_info(accounts_iter)?; // User PDA Account to be created
-    let system_program_info = next_account_info(accounts_iter)?; // System Program
-
-    // User should be the signer
-    if !creator_account_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    // Derive User PDA and check if provided is same as expected
-    let (user_pda, user_bump) = Pubkey::find_program_address(&[b"user", creator_account_info.key.as_ref()], program_id);
-    if *user_account_info.key != user_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // If user PDA already exists
-    if !user_account_info.data_is_empty() {
-        msg!("User already exists");
-        return Ok(());
-    }
-
-    // Calculate rent-exempt
-    let rent = Rent::get()?;
-    let user_space = 32 + 4 as usize;
-    
-    // Create the User PDA Account which is it's global identity
-    invoke_signed(
-        &system_instruction::create_account(
-            creator_account_info.key,
-            user_account_info.key,
-            rent.minimum_balance(user_space),
-            user_space as u64,
-            program_id
-        ),
-        &[creator_account_info.clone(), user_account_info.clone(), system_program_info.clone()],
-        &[&[b"user", creator_account_info.key.as_ref(), &[user_bump]]]
-    )?;
-
-    // Initiallt user is joined in no Funds
-    let funds:Vec<Pubkey> = vec![];
-
-    // Deserialization and Serialization of User Account Data
-    let user_data = UserAccount {
-        user: *creator_account_info.key,
-        funds,
-    };
-    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
-    msg!("User Account created successfully");
-
-    Ok(())
-
-}
-
-fn process_add_member<'a>(
-    program_id: &Pubkey,
-    accounts: &'a [AccountInfo<'a>],
-    fund_name: String,
-) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
-    let member_account_info = next_account_info(accounts_iter)?; // USer to be added 
-    let system_program_info = next_account_info(accounts_iter)?; // System Program
-    let user_account_info = next_account_info(accounts_iter)?; // User Global identity account
-    let user_specific_info = next_account_info(accounts_iter)?; // User Specific pda
-
-    // User should be signer
-    if !member_account_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    // Derive PDAs and check if it is same as provided in accounts
-    let (fund_pda, _fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-    let (user_pda, _user_bump) = Pubkey::find_program_address(&[b"user", member_account_info.key.as_ref()], program_id);
-    if *fund_account_info.key != fund_pda || *user_account_info.key != user_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // Check if Fund exist or not!
-    if fund_account_info.data_is_empty() {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // Deserialize User Data and check if User is already a member of provided Fund
-    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
-    if user_data.funds.contains(fund_account_info.key) {
-        msg!("User is already a member");
-        return Ok(());
-    }
-
-    // Calculate if more lamports are needed for reallocation of User Global Account
-    let current_size = user_account_info.data_len();
-    let new_size = current_size + 32;
-    let rent = Rent::get()?;
-    let new_min_balance = rent.minimum_balance(new_size);
-    let current_balance = user_account_info.lamports();
-    if new_min_balance > current_balance {
-        invoke(
-            &system_instruction::transfer(
-                member_account_info.key,
-                user_account_info.key,
-                new_min_balance - current_balance,
-            ),
-            &[member_account_info.clone(), user_account_info.clone(), system_program_info.clone()],
-        )?;
-    }
-
-    // invoke(
-    //     &system_instruction::transfer(
-    //         member_account_info.key,
-    //         user_account_info.key,
-    //         400_000_000,
-    //     ),
-    //     &[member_account_info.clone(), user_account_info.clone(), system_program_info.clone()]
-    // )?;
-
-    // Reallocate new bytes ofr storage of new Fund addresss
-    user_account_info.realloc(new_size, false)?;
-    user_data.funds.push(*fund_account_info.key);
-    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
-
-    // Deserialize the fund data
-    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
-    let n = fund_data.members;
-    let refund = (4593600 as u64)/(n*(n+1));
-    // Refund to existing members to equally distribute the rent fee
-    for _i in 0..n {
-        let receiver_account_info = next_account_info(accounts_iter)?;
-        invoke(
-            &system_instruction::transfer(
-                member_account_info.key,
-                receiver_account_info.key,
-                refund,
-            ),
-            &[member_account_info.clone(), receiver_account_info.clone(), system_program_info.clone()],
-        )?;
-    }
-
-    fund_data.members += 1;
-    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
-
-    create_user_specific_pda(
-        program_id,
-        member_account_info,
-        system_program_info,
-        fund_account_info,
-        user_specific_info
-    )
-    // Ok(())
-
-}
-
-// fn process_init_deposit_sol(
-//     program_id: &Pubkey,
-//     accounts: &[AccountInfo],
-//     amount: u64,
-//     fund_name: String,
-// ) -> ProgramResult {
-//     let current_time = Clock::get()?.unix_timestamp;
-
-//     let accounts_iter = &mut accounts.iter();
-//     let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
-//     let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
-//     let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
-//     let system_program_info = next_account_info(accounts_iter)?; // System Program
-//     let token_program_info = next_account_info(accounts_iter)?; // Token Program
-//     let governance_token_account_info = next_account_info(accounts_iter)?; // Governance token Account of depositor
-//     let member_account_info = next_account_info(accounts_iter)?; // Depositor Wallet
-//     let user_specific_pda_info = next_account_info(accounts_iter)?; // Depositor's Fund Specific Account
-//     let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar Account
-//     let associated_token_program_info = next_account_info(accounts_iter)?;
-
-//     // Depositor needs to be signer
-//     if !member_account_info.is_signer {
-//         msg!("Required Signer not found");
-//         return Err(FundError::MissingRequiredSignature.into());
-//     }
-
-//     // Derive PDAs and check for equality with provided ones
-//     let (governance_mint, _governance_bump) = Pubkey::find_program_address(&[b"governance", fund_account_info.key.as_ref()], program_id);
-//     let (fund_pda, fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-//     let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_account_info.key.as_ref()], program_id);
-//     let (user_specific_pda, user_specific_bump) = Pubkey::find_program_address(&[b"user", fund_account_info.key.as_ref(), member_account_info.key.as_ref()], program_id);
-//     if *fund_account_info.key != fund_pda ||
-//        *vault_account_info.key != vault_pda ||
-//        *user_specific_pda_info.key != user_specific_pda ||
-//        *governance_mint_info.key != governance_mint {
-//         return Err(FundError::InvalidAccountData.into());
-//     }
-//     let expected_ata = spl_associated_token_account::get_associated_token_address(
-//         member_account_info.key,
-//         governance_mint_info.key,
-//     );
-//     if *governance_token_account_info.key != expected_ata {
-//         return Err(FundError::InvalidTokenAccount.into());
-//     }
-
-//     // If depositor's token account doesn't exist for governance mint, then create it
-//     if governance_token_account_info.data_is_empty() {
-//         invoke(
-//             &spl_associated_token_account::instruction::create_associated_token_account(
-//                 member_account_info.key,
-//                 member_account_info.key,
-//                 governance_mint_info.key,
-//                 token_program_info.key,
-//             ),
-//             &[
-//                 member_account_info.clone(),
-//                 governance_token_account_info.clone(),
-//                 member_account_info.clone(),
-//                 governance_mint_info.clone(),
-//                 token_program_info.clone(),
-//                 system_program_info.clone(),
-//                 associated_token_program_info.clone(),
-//                 rent_sysvar_info.clone(),
-//             ]
-//         )?;
-//     }
-
-//     // Deposit required SOL from depositor to vault account
-//     invoke(
-//         &system_instruction::transfer(
-//             member_account_info.key,
-//             vault_account_info.key,
-//             amount,
-//         ),
-//         &[
-//             member_account_info.clone(),
-//             vault_account_info.clone(),
-//             system_program_info.clone(),
-//         ]
-//     )?;
-
-//     // Now mint equal amount of governance tokens to depositor's governance token account
-//     invoke_signed(
-//         &spl_token::instruction::mint_to(
-//             token_program_info.key,
-//             governance_mint_info.key,
-//             governance_token_account_info.key,
-//             fund_account_info.key,
-//             &[],
-//             amount,
-//         )?,
-//         &[
-//             governance_mint_info.clone(),
-//             governance_token_account_info.clone(),
-//             fund_account_info.clone(),
-//             token_program_info.clone(),
-//             // rent_sysvar_info.clone(),
-//         ],
-//         &[&[b"fund", fund_name.as_bytes(), &[fund_bump]]],
-//     )?;
-
-//     // In vault account, set the last deposit time
-//     let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
-//     vault_data.last_deposit_time = current_time;
-//     vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
-
-
-//     // In fund account increase the deposited amount (unit lamports)
-//     let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
-//     fund_data.total_deposit += amount;
-//     fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
-
-//     Ok(())
-// }
-
-fn process_init_deposit_token(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amount: u64,
-    fund_name: String,
-) -> ProgramResult {
-    let current_time = Clock::get()?.unix_timestamp;
-
-    let accounts_iter = &mut accounts.iter();
-    let member_account_info = next_account_info(accounts_iter)?; // Depositor wallet
-    let member_ata_info = next_account_info(accounts_iter)?; // Depositor's ATA for the depositing mint
-    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
-    let vault_ata_info = next_account_info(accounts_iter)?; // Vault PDA's ATA for the depositing mint
-    let mint_account_info = next_account_info(accounts_iter)?; // Mint account of token to be deposited
-    let token_program_info = next_account_info(accounts_iter)?; // Token Progarm
-    let ata_program_info = next_account_info(accounts_iter)?; // Associated Token Program
-    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
-    let user_specific_pda_info = next_account_info(accounts_iter)?; // USER Specific PDA
-    let system_program_info = next_account_info(accounts_iter)?; // System program
-    let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar Account
-    let governance_token_account_info = next_account_info(accounts_iter)?; // Governance Token Account of depositor
-    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
-    // let temp_wsol_account_info = next_account_info(accounts_iter)?;
-
-    // Depositor should be signer
-    if !member_account_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    // Derive the PDAs and check for equality with provided ones
-    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_account_info.key.as_ref()], program_id);
-    let (fund_pda, fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-    let (user_specific_pda, _user_specific_bump) = Pubkey::find_program_address(&[b"user", fund_pda.as_ref(), member_account_info.key.as_ref()], program_id);
-    if *vault_account_info.key != vault_pda || *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_specific_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-    let expected_ata = spl_associated_token_account::get_associated_token_address(
-        member_account_info.key,
-        governance_mint_info.key,
-    );
-    if *governance_token_account_info.key != expected_ata {
-        return Err(FundError::InvalidTokenAccount.into());
-    }
-
-    // If depositor's governance token account doesn't exist, create one
-    if governance_token_account_info.data_is_empty() {
-        invoke(
-            &spl_associated_token_account::instruction::create_associated_token_account(
-                member_account_info.key,
-                member_account_info.key,
-                governance_mint_info.key,
-                token_program_info.key,
-            ),
-            &[
-                member_account_info.clone(),
-                governance_token_account_info.clone(),
-                token_program_info.clone(),
-                governance_mint_info.clone(),
-                rent_sysvar_info.clone(),
-            ]
-        )?;
-    }
-
-    let rent =Rent::get()?;
-    let rent_req = rent.minimum_balance(TokenAccount::LEN);
-
-    // If vault's token account account for the depositing mint doesn't exist, create it
-    if vault_ata_info.data_is_empty() {
-        msg!("Creating Vault ATA...");
-
-        invoke_signed(
-            &create_associated_token_account(
-                &vault_pda,
-                member_account_info.key,
-                mint_account_info.key,
-                token_program_info.key
-            ),
-            &[
-                member_account_info.clone(),
-                vault_ata_info.clone(),
-                vault_account_info.clone(),
-                mint_account_info.clone(),
-                system_program_info.clone(),
-                token_program_info.clone(),
-                ata_program_info.clone(),
-                rent_sysvar_info.clone(),
-            ],
-            &[&[b"vault", fund_account_info.key.as_ref(), &[vault_bump]]]
-        )?;
-    }
-
-    let mint: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
-
-    if *mint_account_info.key == mint {
-        invoke(
-            &system_instruction::create_account(
-                member_account_info.key,
-                member_ata_info.key,
-                rent_req+amount,
-                TokenAccount::LEN as u64,
-                token_program_info.key,
-            ),
-            &[
-                member_account_info.clone(),
-                member_ata_info.clone(),
-                token_program_info.clone(),
-                system_program_info.clone(),
-            ]
-        )?;
-
-        invoke(
-            &spl_token::instruction::initialize_account(
-                token_program_info.key,
-                member_ata_info.key,
-                mint_account_info.key,
-                member_account_info.key,
-            )?,
-            &[
-                token_program_info.clone(),
-                member_ata_info.clone(),
-                mint_account_info.clone(),
-                member_account_info.clone(),
-            ]
-        )?;
-
-        invoke(
-            &spl_token::instruction::transfer(
-                token_program_info.key,
-                member_ata_info.key,
-                vault_ata_info.key,
-                member_account_info.key,
-                &[],
-                amount
-            )?,
-            &[
-                token_program_info.clone(),
-                member_ata_info.clone(),
-                vault_ata_info.clone(),
-                member_account_info.clone(),
-            ]
-        )?;
-    } else {
-        msg!("Transferring tokens...");
-        // Now transfer the required number of tokens from depositor's token account to vault's token account
-        invoke_signed(
-            &spl_token::instruction::transfer(
-                token_program_info.key,
-                member_ata_info.key,
-                vault_ata_info.key,
-                member_account_info.key,
-                &[],
-                amount,
-            )?,
-            &[
-                member_ata_info.clone(),
-                vault_ata_info.clone(),
-                member_account_info.clone(),
-                token_program_info.clone(),
-            ],
-            &[]
-        )?;
-    }
-
-
-    // Now mint equivalent quantity of governance tokens to the depositor's governance token account
-    invoke_signed(
-        &spl_token::instruction::mint_to(
-            token_program_info.key,
-            governance_mint_info.key,
-            governance_token_account_info.key,
-            fund_account_info.key,
-            &[],
-            amount,
-        )?,
-        &[
-            governance_mint_info.clone(),
-            governance_token_account_info.clone(),
-            fund_account_info.clone(),
-            token_program_info.clone(),
-        ],
-        &[&[b"fund", fund_name.as_bytes(), &[fund_bump]]],
-    )?;
-
-    // In vault account, set the last deposit time
-    let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
-    vault_data.last_deposit_time = current_time;
-    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
-
-    // In fund account increase the deposited amount (unit lamports)
-    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
-    fund_data.total_deposit += amount;
-    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
-
-    Ok(())
-}
-
-fn process_init_investment_proposal(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amounts: Vec<u64>,
-    // dex_tags: Vec<u8>,
-    deadline: i64,
-    fund_name: String,
-) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet
-    let user_specific_pda_info = next_account_info(accounts_iter)?; // Proposer's Fund-specific Account
-    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
-    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
-    let system_program_info = next_account_info(accounts_iter)?; // System Program
-
-    // Proposer needs to be signer
-    if !proposer_account_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    // Derive PDAs and check for equality with the provided ones
-    let (fund_pda, _fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-    let (user_pda, _user_bump) = Pubkey::find_program_address(&[b"user", fund_pda.as_ref(), proposer_account_info.key.as_ref()], program_id);
-    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
-    let (proposal_pda, proposal_bump) = Pubkey::find_program_address(
-        &[
-            b"proposal-investment",
-            proposer_account_info.key.as_ref(),
-            &[user_data.num_proposals],
-            fund_account_info.key.as_ref()
-        ],
-        program_id
-    );
-    if *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_pda || *proposal_account_info.key != proposal_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    // Rent Calculation
-    let rent = Rent::get()?;
-    let proposal_space = 43 + 32 + 4 + amounts.len()*73;
-    let total_rent = rent.minimum_balance(proposal_space);
-
-    // Create Proposal Account
-    invoke_signed(
-        &system_instruction::create_account(
-            proposer_account_info.key,
-            proposal_account_info.key,
-            total_rent,
-            proposal_space as u64,
-            program_id
-        ),
-        &[
-            proposal_account_info.clone(),
-            proposer_account_info.clone(),
-            system_program_info.clone(),
-        ],
-        &[&[
-            b"proposal-investment",
-            proposer_account_info.key.as_ref(),
-            &[user_data.num_proposals],
-            fund_account_info.key.as_ref(),
-            &[proposal_bump]
-        ]]
-    )?;
-
-    // Extract From Assets Mint
-    let from_assets_info : Vec<&AccountInfo> = accounts_iter
-        .take(amounts.len())
-        .collect();
-    if from_assets_info.len() != amounts.len() {
-        return Err(FundError::InvalidAccountData.into());
-    }
-    let from_assets_mints: Vec<Pubkey> = from_assets_info.iter().map(|m| *m.key).collect();
-
-    // Extract To Assets Mint
-    let to_assets_info: Vec<&AccountInfo> = accounts_iter
-        .take(amounts.len())
-        .collect();
-    if to_assets_info.len() != amounts.len() {
-        return Err(FundError::InvalidAccountData.into());
-    }
-    let to_assets_mints: Vec<Pubkey> = to_assets_info.iter().map(|m| *m.key).collect();
-
-    // Deserialization and Serialization of Proposal Account data
-    let proposal_data = InvestmentProposalAccount {
-        fund: *fund_account_info.key,
-        proposer: *proposer_account_info.key,
-        from_assets: from_assets_mints,
-        to_assets: to_assets_mints,
-        amounts,
-        // dex_tags,
-        deadline,
-        votes_yes: 0,
-        votes_no: 0,
-        executed: false
-    };
-    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
-    user_data.num_proposals += 1;
-    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
-
-    Ok(())
-}
-
-fn process_vote_on_proposal(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    vote: u8,
-    fund_name: Vec<u8>,
-) -> ProgramResult {
-    let current_time = Clock::get()?.unix_timestamp;
-
-    let accounts_iter = &mut accounts.iter();
-    let voter_account_info = next_account_info(accounts_iter)?;
-    let vote_account_info = next_account_info(accounts_iter)?;
-    let proposal_account_info = next_account_info(accounts_iter)?;
-    let system_program_info = next_account_info(accounts_iter)?;
-    let user_specific_pda_info = next_account_info(accounts_iter)?;
-    let fund_account_info = next_account_info(accounts_iter)?;
-    let governance_token_mint_info = next_account_info(accounts_iter)?;
-    let voter_token_account_info = next_account_info(accounts_iter)?;
-
-    if !voter_account_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    let (fund_pda, _fund_bump) = Pubkey::find_program_address(&[b"fund", &fund_name], program_id);
-    let (user_pda, _user_bump) = Pubkey::find_program_address(&[b"user", fund_pda.as_ref(), voter_account_info.key.as_ref()], program_id);
-    let user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
-    let (proposal_pda, _proposal_bump) = Pubkey::find_program_address(&[b"proposal-investment", voter_account_info.key.as_ref(), &[user_data.num_proposals]], program_id);
-    let (vote_pda, _vote_bump) = Pubkey::find_program_address(&[b"vote", voter_account_info.key.as_ref(), proposal_pda.as_ref()], program_id);
-    let token_account = spl_associated_token_account::get_associated_token_address(
-        voter_account_info.key,
-        governance_token_mint_info.key
-    );
-
-    if *fund_account_info.key != fund_pda ||
-        *user_specific_pda_info.key != user_pda ||
-        *proposal_account_info.key != proposal_pda ||
-        *vote_account_info.key != vote_pda ||
-        token_account != *voter_token_account_info.key {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
-    if fund_data.governance_mint != *governance_token_mint_info.key {
-        return Err(FundError::InvalidGovernanceMint.into());
-    }
-
-    let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
-    if proposal_data.deadline < current_time {
-        return Err(FundError::VotingCeased.into());
-    }
-
-    let rent = Rent::get()?;
-    let vote_space = 33 as usize;
-    let total_rent = rent.minimum_balance(vote_space);
-
-    if vote_account_info.data_is_empty() {
-        invoke(
-            &system_instruction::create_account(
-                voter_account_info.key,
-                vote_account_info.key,
-                total_rent,
-                vote_space as u64,
-                program_id
-            ),
-            &[
-                voter_account_info.clone(),
-                vote_account_info.clone(),
-                system_program_info.clone(),
-            ]
-        )?;
-
-        let token_account_data = TokenAccount::unpack(&voter_token_account_info.data.borrow())?;
-        let voting_power = token_account_data.amount;
-        
-        if vote == 1 {
-            proposal_data.votes_yes += voting_power;
-        } else {
-            proposal_data.votes_no += voting_power;
-        }
-
-        proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
-
-        let vote_data = VoteAccount {
-            voter: *voter_account_info.key,
-            vote,
-        };
-
-        vote_data.serialize(&mut &mut vote_account_info.data.borrow_mut()[..])?;
-    } else {
-        return Err(FundError::AlreadyVoted.into());
-    }
-
-    Ok(())
-}
-
-fn process_init_rent_account(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-) -> ProgramResult {
-    let accounts_iter = &mut accounts.iter();
-    let rent_account_info = next_account_info(accounts_iter)?;
-    let system_program_info = next_account_info(accounts_iter)?;
-    let god_father_info = next_account_info(accounts_iter)?;
-
-    if !god_father_info.is_signer {
-        return Err(FundError::MissingRequiredSignature.into());
-    }
-
-    let (rent_pda, rent_bump) = Pubkey::find_program_address(&[b"rent"], program_id);
-
-    if *rent_account_info.key != rent_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    let rent = Rent::get()?;
-    let data_len = 0 as usize;
-    let rent_exemption_amount = rent.minimum_balance(data_len);
-
-    invoke_signed(
-        &system_instruction::create_account(
-            god_father_info.key,
-            rent_account_info.key,
-            rent_exemption_amount,
-            data_len as u64,
-            program_id
-        ),
-        &[god_father_info.clone(), rent_account_info.clone(), system_program_info.clone()],
-        &[&[b"rent", &[rent_bump]]]
-    )?;
-
-    Ok(())
-}
-
-
-//    LEAVING FUND
-//    delete fund specific pda
-//    change member array size in fund pda
-//    change User account pda's size
-
-fn process_leave_fund(
-    program_id: &Pubkey,
-    fund_name: String,
-    accounts: &[AccountInfo],
-    
-) -> ProgramResult {
-
-    // let current_time = Clock::get()?.unix_timestamp;
-
-    let accounts_iter = &mut accounts.iter();
-    let member_wallet_info =next_account_info(accounts_iter)?;
-    let user_specific_info = next_account_info(accounts_iter)?; // to be deleted
-    let fund_account_info= next_account_info(accounts_iter)?;  // to change number of members
-    let user_account_info=next_account_info(accounts_iter)?;   // to change fund details
-    // let system_program_info = next_account_info(accounts_iter)?;  // to delete fund specific account
-    // let voter_account_info = next_account_info(accounts_iter)?;
-    // let proposal_account_info = next_account_info(accounts_iter)?;
-
-    if !member_wallet_info.is_signer {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-
-    let (fund_pda, _fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
-    let (user_specific_pda, _user_specific_bump) = Pubkey::find_program_address(&[b"user", fund_pda.as_ref(), member_wallet_info.key.as_ref()], program_id);
-    let (user_pda, _user_bump) = Pubkey::find_program_address(&[b"user", member_wallet_info.key.as_ref()], program_id);
-    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
-    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
-    // let (proposal_pda, _proposal_bump) = Pubkey::find_program_address(&[b"proposal-investment", user_account_info.key.as_ref(), &[user_data.num_proposals]], program_id);
-    // let proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
-    if *fund_account_info.key != fund_pda ||
-    *user_specific_info.key != user_specific_pda ||
-    *user_account_info.key != user_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-
-
-    if !user_specific_info.data_is_empty(){
-        let lamports = **user_specific_info.try_borrow_lamports()?;
-        **member_wallet_info.try_borrow_mut_lamports()? += lamports;
-        **user_specific_info.try_borrow_mut_lamports()? = 0;
-    
-        // let mut data = user_specific_info.try_borrow_mut_data()?;
-        // for byte in data.iter_mut() {
-        //     *byte = 0;
-        // }
-    
-        msg!("User-specific fund account closed and lamports sent to user");
-        
-    }
-    
-    let current_rent = user_account_info.lamports();
-
-    let mut flag = false;
-
-    user_data.funds.retain(|key| {
-        let keep = key != fund_account_info.key;
-        if !keep {
-            flag = true;
-        }
-        keep
-    });
-
-    if flag {
-
-        fund_data.members-=1;
-
-        let rent = Rent::get()?;
-
-        let current_size = user_account_info.data_len();
-        let new_size= current_size-32;
-        let new_rent = rent.minimum_balance(new_size);
-    
-        if new_rent < current_rent {
-            // let lamports = **user_account_info.try_borrow_lamports()?;
-            **user_account_info.try_borrow_mut_lamports()? -= current_rent-new_rent;
-            **member_wallet_info.try_borrow_mut_lamports()? += current_rent-new_rent;
-        }
-
-        user_account_info.realloc(new_size, false)?;
-    }
-
-    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
-    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
-
-    Ok(())
-}
-
-
-// fn process_delete_fund(
-//     _program_id: &Pubkey,
-//     accounts: &[AccountInfo],
-//     // fund_name: String,
-// ) -> ProgramResult {
-//     let accounts_iter = &mut accounts.iter();
-//     let fund_account_info = next_account_info(accounts_iter)?;
-//     let recipient_account_info = next_account_info(accounts_iter)?;
-//     let vault_account_info = next_account_info(accounts_iter)?;
-//     let governance_mint_info = next_account_info(accounts_iter)?;
-
-//     **recipient_account_info.lamports.borrow_mut() += **governance_mint_info.lamports.borrow();
-//     **governance_mint_info.lamports.borrow_mut() = 0;
-//     let mut gov_data = governance_mint_info.try_borrow_mut_data()?;
-//     for byte in gov_data.iter_mut() {
-//         *byte = 0;
-//     }
-
-//     **recipient_account_info.lamports.borrow_mut() += **vault_account_info.lamports.borrow();
-//     **vault_account_info.lamports.borrow_mut() = 0;
-//     let mut gov_data = vault_account_info.try_borrow_mut_data()?;
-//     for byte in gov_data.iter_mut() {
-//         *byte = 0;
-//     }
-
-//     **recipient_account_info.lamports.borrow_mut() += **fund_account_info.lamports.borrow();
-//     **fund_account_info.lamports.borrow_mut() = 0;
-//     let mut gov_data = fund_account_info.try_borrow_mut_data()?;
-//     for byte in gov_data.iter_mut() {
-//         *byte = 0;
-//     }
-
-//     Ok(())
-// }
-
-/* fn process_execute_proposal(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    fund_name: Vec<u8>,
-) -> ProgramResult {
-    let current_time = Clock::get()?.unix_timestamp;
-
-    let accounts_iter = &mut accounts.iter();
-    let proposal_account_info = next_account_info(accounts_iter)?;
-    let fund_account_info = next_account_info(accounts_iter)?;
-    let vault_account_info = next_account_info(accounts_iter)?;
-    let system_program_info = next_account_info(accounts_iter)?;
-    let token_program_info = next_account_info(accounts_iter)?;
-}
-*/
-
-fn create_user_specific_pda<'a>(
-    program_id: &Pubkey,
-    member_wallet_info: &'a AccountInfo<'a>,
-    system_program_info: &'a AccountInfo<'a>,
-    fund_account_info: &'a AccountInfo<'a>,
-    user_specific_info: &'a AccountInfo<'a>
-) -> ProgramResult {
-
-    let current_time = Clock::get()?.unix_timestamp;
-
-    let (user_specific_pda, user_specific_bump) = Pubkey::find_program_address(&[b"user", fund_account_info.key.as_ref(), member_wallet_info.key.as_ref()], program_id);
-
-    if *user_specific_info.key != user_specific_pda {
-        return Err(FundError::InvalidAccountData.into());
-    }
-
-    if !user_specific_info.data_is_empty() {
-        msg!("User already exists");
-        return Ok(());
-    }
-
-    let rent = Rent::get()?;
-    let size = 90 as usize;
-
-    invoke_signed(
-        &system_instruction::create_account(
-            member_wallet_info.key,
-            user_specific_info.key,
-            rent.minimum_balance(size),
-            size as u64,
-            program_id
-        ),
-        &[member_wallet_info.clone(),user_specific_info.clone(),system_program_info.clone()],
-        &[&[b"user", fund_account_info.key.as_ref(), member_wallet_info.key.as_ref(),&[user_specific_bump]]]
-    )?;
-
-    let mut user_data= UserSpecificAccount::try_from_slice(&user_specific_info.data.borrow())?;
-
-        user_data.fund = *fund_account_info.key;
-        user_data.is_active = true;
-        user_data.join_time = current_time;
-        user_data.pubkey = *member_wallet_info.key;
-
-    user_data.serialize(&mut &mut user_specific_info.data.borrow_mut()[..])?;
-
-    Ok(())
-}
\ No newline at end of file
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program:: pubkey;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, clock::Clock, entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE}, msg, program::{invoke, invoke_signed, set_return_data}, program_error::ProgramError, program_pack::Pack, pubkey:: Pubkey, system_instruction, system_program, sysvar::{rent::Rent, Sysvar}
+    // instruction::{Instruction},
+};
+use solana_program::program_option::COption;
+use spl_token::state::Account as TokenAccount;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use crate::{
+    dex::{
+        build_jupiter_swap_ix, build_orca_swap_ix, is_dex_allowed, is_supported_dex, orca_quote, orca_swap_direction, wrap_sol, SwapRoute,
+        DEX_JUPITER, DEX_ORCA_WHIRLPOOL, JUPITER_EVENT_AUTHORITY, JUPITER_PROGRAM_ID, ORCA_POOL_ACCOUNTS, ORCA_WHIRLPOOL_PROGRAM_ID,
+    },
+    errors::FundError,
+    events,
+    instruction::FundInstruction,
+    state::{AcceptedMint, ConfigChange, FundAccount, FundAccountV1, FundConfig, InvestmentProposalAccount, ProposalRegistry, UserAccount, UserSpecificAccount, UserSpecificAccountV1, VaultAccount, VaultAccountV1, VoteAccount, PROPOSAL_KIND_ADD_MEMBER, PROPOSAL_KIND_INVESTMENT, PROPOSAL_KIND_CONFIG_CHANGE, PROPOSAL_KIND_REMOVE_MEMBER, PROPOSAL_KIND_SET_ROLES, PROPOSAL_KIND_WITHDRAWAL, FUND_ACCOUNT_VERSION, PROPOSAL_ACCOUNT_VERSION, USER_SPECIFIC_ACCOUNT_VERSION, VAULT_ACCOUNT_VERSION, VOTE_ACCOUNT_VERSION, ROLE_ALL, ROLE_PROPOSER, VOTING_MODE_QUADRATIC, VOTING_MODE_TIME_WEIGHTED, VOTING_MODE_TOKEN_WEIGHTED},
+    oracle::{read_price, token_value_in_lamports, DEFAULT_MAX_PRICE_AGE, SOL_USD_PRICE_FEED},
+    utils::{assert_mint, assert_owned_by, assert_program, assert_writable, derive_fund_pda, derive_governance_mint_pda, derive_proposal_registry_pda, derive_reserve_pda, derive_user_pda, derive_user_specific_pda, fresh_vault_value, governance_tokens_for_deposit, governance_tokens_for_withdrawal, isqrt, required_role, transfer_from_vault, vault_spare_lamports, time_weighted_power, validate_deadline, validate_fund_config, DEFAULT_MIN_VOTING_PERIOD, EXECUTION_GRACE_PERIOD, FUND_SEED, GOVERNANCE_SEED, MAX_ACCEPTED_MINTS, MAX_FUND_MEMBERS, MAX_PROPOSAL_WINDOW, MIN_FIRST_DEPOSIT, PROPOSAL_REGISTRY_SEED, RESERVE_SEED, USER_SEED},
+    views::{FundSummary, FundValuation, MemberPosition, MembersPage, ProposalStatus, ProposalsPage, MEMBERS_PAGE_SIZE, PROPOSALS_PAGE_SIZE},
+};
+use mpl_token_metadata::types::DataV2;
+use mpl_token_metadata::instructions::{CreateMetadataAccountV3, CreateMetadataAccountV3InstructionArgs};
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+pub fn process_instruction<'a>(
+    program_id: &Pubkey,
+    accounts: &'a[AccountInfo<'a>],
+    data: &[u8]
+) -> ProgramResult {
+    let instruction = FundInstruction::unpack(data)?;
+    match instruction {
+
+        FundInstruction::InitFundAccount { privacy, name, config, fund_name} => {
+            msg!("Instruction: Init Fund Account");
+            process_init_fund_account(program_id, accounts, name, config, fund_name, privacy)
+        }
+
+        FundInstruction::InitUserAccount { user_seed } => {
+            msg!("Instruction: Init User Account");
+            process_init_user_account(program_id, accounts, user_seed)
+        }
+
+        FundInstruction::AddFundMember { fund_name } => {
+            msg!("Instruction: Add Fund Member");
+            process_add_member(program_id, accounts, fund_name)
+        }
+
+        // FundInstruction::InitDepositSol { amount , fund_name} => {
+        //     msg!("Instruction: Init Deposit");
+        //     process_init_deposit_sol(program_id, accounts, amount, fund_name)
+        // }
+
+        FundInstruction::InitDepositToken { amount, fund_name } => {
+            msg!("Instruction: Init Deposit Token");
+            process_init_deposit_token(program_id, accounts, amount, fund_name)
+        }
+
+        FundInstruction::InitProposalInvestment { 
+            proposal_kind,
+            amounts,
+            min_out,
+            dex_tags,
+            deadline,
+            fund_name,
+        } => {
+            msg!("Instruction: Init Proposal");
+            process_init_investment_proposal(program_id, accounts, proposal_kind, amounts, min_out, dex_tags, deadline, fund_name)
+        }
+
+        FundInstruction::InitProposalMembership { proposal_kind, target_member, deadline, fund_name } => {
+            msg!("Instruction: Init Membership Proposal");
+            process_init_governance_proposal(program_id, accounts, proposal_kind, target_member, 0, ConfigChange::default(), 0, deadline, fund_name)
+        }
+
+        FundInstruction::InitProposalWithdrawal { recipient, lamports, deadline, fund_name } => {
+            msg!("Instruction: Init Withdrawal Proposal");
+            process_init_governance_proposal(program_id, accounts, PROPOSAL_KIND_WITHDRAWAL, recipient, lamports, ConfigChange::default(), 0, deadline, fund_name)
+        }
+
+        FundInstruction::InitProposalConfigChange { config_change, deadline, fund_name } => {
+            msg!("Instruction: Init Config Change Proposal");
+            process_init_governance_proposal(program_id, accounts, PROPOSAL_KIND_CONFIG_CHANGE, Pubkey::default(), 0, config_change, 0, deadline, fund_name)
+        }
+
+        FundInstruction::InitProposalSetRoles { target_member, roles, deadline, fund_name } => {
+            msg!("Instruction: Init Set Roles Proposal");
+            process_init_governance_proposal(program_id, accounts, PROPOSAL_KIND_SET_ROLES, target_member, 0, ConfigChange::default(), roles, deadline, fund_name)
+        }
+
+        FundInstruction::ChangeVote { vote, fund_name } => {
+            msg!("Instruction: Change Vote");
+            process_change_vote(program_id, accounts, vote, fund_name)
+        }
+
+        FundInstruction::CloseFund { fund_name } => {
+            msg!("Instruction: Close Fund");
+            process_close_fund(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::Vote {vote, fund_name} => {
+            msg!("Instruction: Voting on Proposal");
+            process_vote_on_proposal(program_id, accounts, vote, fund_name)
+        }
+
+        FundInstruction::LeaveFund { fund_name } => {
+            msg!("Instruction: Leave Fund");
+            process_leave_fund(program_id, fund_name, accounts)
+        }
+
+        FundInstruction::Execute { proposal, routes } => {
+            msg!("Instruction: Execute Proposal");
+            process_execute(program_id, accounts, proposal, routes)
+        }
+
+        FundInstruction::SetPaused { paused, nonce, fund_name } => {
+            msg!("Instruction: Set Paused");
+            process_set_paused(program_id, accounts, paused, nonce, fund_name)
+        }
+
+        FundInstruction::CloseProposal { fund_name } => {
+            msg!("Instruction: Close Proposal");
+            process_close_proposal(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::GetFundSummary { fund_name } => {
+            msg!("Instruction: Get Fund Summary");
+            process_get_fund_summary(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::GetProposalStatus { fund_name } => {
+            msg!("Instruction: Get Proposal Status");
+            process_get_proposal_status(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::GetMemberPosition { fund_name } => {
+            msg!("Instruction: Get Member Position");
+            process_get_member_position(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::DelegateVote { delegate, fund_name } => {
+            msg!("Instruction: Delegate Vote");
+            process_delegate_vote(program_id, accounts, delegate, fund_name)
+        }
+
+        FundInstruction::DonateSol { amount, fund_name } => {
+            msg!("Instruction: Donate Sol");
+            process_donate_sol(program_id, accounts, amount, fund_name)
+        }
+
+        FundInstruction::CancelProposal { fund_name } => {
+            msg!("Instruction: Cancel Proposal");
+            process_cancel_proposal(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::BatchVote { votes, fund_name } => {
+            msg!("Instruction: Batch Vote");
+            process_batch_vote(program_id, accounts, votes, fund_name)
+        }
+
+        FundInstruction::RegisterFundMembership { fund_name } => {
+            msg!("Instruction: Register Fund Membership");
+            process_register_fund_membership(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::CloseVaultTokenAccount { fund_name } => {
+            msg!("Instruction: Close Vault Token Account");
+            process_close_vault_token_account(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::FreezeDeposits { frozen, nonce, fund_name } => {
+            msg!("Instruction: Freeze Deposits");
+            process_freeze_deposits(program_id, accounts, frozen, nonce, fund_name)
+        }
+
+        FundInstruction::GetMembers { offset, fund_name } => {
+            msg!("Instruction: Get Members");
+            process_get_members(program_id, accounts, offset, fund_name)
+        }
+
+        FundInstruction::RefreshVaultValue { fund_name } => {
+            msg!("Instruction: Refresh Vault Value");
+            process_refresh_vault_value(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::TransferCreator { new_creator, nonce, fund_name } => {
+            msg!("Instruction: Transfer Creator");
+            process_transfer_creator(program_id, accounts, new_creator, nonce, fund_name)
+        }
+
+        FundInstruction::SimulateExecute { proposal, routes } => {
+            msg!("Instruction: Simulate Execute");
+            process_simulate_execute(program_id, accounts, proposal, routes)
+        }
+
+        FundInstruction::GetOpenProposals { offset, fund_name } => {
+            msg!("Instruction: Get Open Proposals");
+            process_get_open_proposals(program_id, accounts, offset, fund_name)
+        }
+
+        FundInstruction::MigrateFund { fund_name } => {
+            msg!("Instruction: Migrate Fund");
+            process_migrate_fund(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::GetFundValuation { fund_name } => {
+            msg!("Instruction: Get Fund Valuation");
+            process_get_fund_valuation(program_id, accounts, fund_name)
+        }
+
+        FundInstruction::WithdrawSol { amount, fund_name } => {
+            msg!("Instruction: Withdraw Sol");
+            process_withdraw_sol(program_id, accounts, amount, fund_name)
+        }
+        // FundInstruction::DeleteFund {  } => {
+        //     msg!("Instruction: Delete Fund");
+        //     process_delete_fund(program_id, accounts)
+        // }
+
+        _ => Err(FundError::InvalidInstruction.into()),
+    }
+}
+
+fn process_init_fund_account<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    name: [u8; 32],
+    mut config: FundConfig,
+    fund_name: String,
+    privacy: u8,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA Account
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program (2020)
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA Account
+    let creator_wallet_info = next_account_info(accounts_iter)?; // Creator Wallet Address
+    let metadata_account_info = next_account_info(accounts_iter)?; // Metadat PDA for Governance Mint
+    let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar
+    let token_metadata_program_info = next_account_info(accounts_iter)?; // Token Metadata Program
+    let user_account_info = next_account_info(accounts_iter)?; // Global User Account
+    let user_specific_info = next_account_info(accounts_iter)?;
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+    assert_program(token_program_info, &spl_token::id())?;
+    assert_program(token_metadata_program_info, &TOKEN_METADATA_PROGRAM_ID)?;
+
+    // Creator should be signer
+    if !creator_wallet_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Reject out-of-range governance settings before creating anything
+    if config.min_voting_period == 0 {
+        config.min_voting_period = DEFAULT_MIN_VOTING_PERIOD;
+    }
+    if config.max_price_age == 0 {
+        config.max_price_age = DEFAULT_MAX_PRICE_AGE;
+    }
+    validate_fund_config(&config)?;
+
+    // Remaining accounts are the mints the Fund accepts for deposits, each followed by the Pyth
+    // price account it's valued with. WSOL is valued with the SOL/USD feed, any other feed must
+    // hold a current price. Init only ever creates the creator's membership, other members join
+    // through their own instructions, so its cost is bounded by MAX_ACCEPTED_MINTS alone.
+    let mut accepted_mints: Vec<AcceptedMint> = vec![];
+    while let Some(mint_info) = accounts_iter.next() {
+        let price_feed_info = next_account_info(accounts_iter)?; // Mint's Pyth price feed
+        if *mint_info.owner != spl_token::id() {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        if accepted_mints.len() >= MAX_ACCEPTED_MINTS || accepted_mints.iter().any(|accepted| accepted.mint == *mint_info.key) {
+            return Err(FundError::InvalidFundConfig.into());
+        }
+        if *mint_info.key == spl_token::native_mint::id() {
+            if *price_feed_info.key != SOL_USD_PRICE_FEED {
+                return Err(FundError::InvalidOracleAccount.into());
+            }
+        } else {
+            read_price(price_feed_info, config.max_price_age)?;
+        }
+        accepted_mints.push(AcceptedMint { mint: *mint_info.key, price_feed: *price_feed_info.key });
+    }
+    if accepted_mints.is_empty() {
+        accepted_mints.push(AcceptedMint { mint: spl_token::native_mint::id(), price_feed: SOL_USD_PRICE_FEED });
+    }
+
+    // Deriving required PDAs
+    let (fund_pda, fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    let (governance_mint, governance_bump) = derive_governance_mint_pda(program_id, &fund_pda);
+    let (user_pda, _user_bump) = derive_user_pda(program_id, creator_wallet_info.key);
+
+    // Check if any of the provided PDA differes from the derived
+    if *fund_account_info.key != fund_pda ||
+       *vault_account_info.key != vault_pda ||
+       *governance_mint_info.key != governance_mint ||
+       *user_account_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Check if the an account already exists on that PDA
+    if fund_account_info.lamports() > 0 {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Calculate Rent
+    let rent = &Rent::from_account_info(rent_sysvar_info)?;
+    let fund_space = FundAccount::space(1, accepted_mints.len()); // The creator as first member and accepted mints
+    let vault_space = VaultAccount::LEN;
+    let mint_space = spl_token::state::Mint::LEN;
+
+    // Creating the Fund Account PDA
+    invoke_signed(
+        &system_instruction::create_account(
+            creator_wallet_info.key,
+            fund_account_info.key,
+            rent.minimum_balance(fund_space),
+            fund_space as u64,
+            program_id,
+        ),
+        &[creator_wallet_info.clone(), fund_account_info.clone(), system_program_info.clone()],
+        &[&[FUND_SEED, fund_name.as_bytes(), &[fund_bump]]]
+    )?;
+
+    // Creating the Vault Account PDA
+    invoke_signed(
+        &system_instruction::create_account(
+            creator_wallet_info.key,
+            vault_account_info.key,
+            rent.minimum_balance(vault_space),
+            vault_space as u64,
+            program_id,
+        ),
+        &[creator_wallet_info.clone(), vault_account_info.clone(), system_program_info.clone()],
+        &[&[b"vault", fund_pda.as_ref(), &[vault_bump]]],
+    )?;
+
+    // Creating the Governance Mint account
+    invoke_signed(
+        &system_instruction::create_account(
+            creator_wallet_info.key,
+            governance_mint_info.key,
+            rent.minimum_balance(mint_space),
+            mint_space as u64,
+            token_program_info.key,
+        ),
+        &[creator_wallet_info.clone(), governance_mint_info.clone(), system_program_info.clone()],
+        &[&[GOVERNANCE_SEED, fund_pda.as_ref(), &[governance_bump]]],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::initialize_mint(
+            token_program_info.key,
+            governance_mint_info.key,
+            fund_account_info.key,
+            None,
+            config.governance_decimals,
+        )?,
+        &[governance_mint_info.clone(), token_program_info.clone(), rent_sysvar_info.clone()],
+        &[&[GOVERNANCE_SEED, fund_pda.as_ref(), &[governance_bump]]],
+    )?;
+
+    // Deriving PDA to store Mint metadata
+    let (metadata_pda, _bump) = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            governance_mint_info.key.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    );
+
+    // Token metadata
+    let token_name = fund_name.clone();
+    let token_symbol = String::from("GOV");
+    let token_uri = "".to_string();
+
+    // Instruction to create Mtadata account at derived PDA
+    let create_metadata_ix = CreateMetadataAccountV3 {
+        metadata: metadata_pda,
+        mint: *governance_mint_info.key,
+        mint_authority: *fund_account_info.key,
+        payer: *creator_wallet_info.key,
+        update_authority: (*fund_account_info.key, true),
+        system_program: *system_program_info.key,
+        rent: None,
+    }.instruction(CreateMetadataAccountV3InstructionArgs {
+        data: DataV2 {
+            name: token_name,
+            symbol: token_symbol,
+            uri: token_uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        },
+        is_mutable: true,
+        collection_details: None,
+    });
+
+    // Invoking instruction to create Metadata PDA account
+    invoke_signed(
+        &create_metadata_ix,
+        &[
+            metadata_account_info.clone(),
+            governance_mint_info.clone(),
+            fund_account_info.clone(),
+            fund_account_info.clone(),
+            creator_wallet_info.clone(),
+            system_program_info.clone(),
+            rent_sysvar_info.clone(),
+            token_metadata_program_info.clone(),
+        ],
+        &[&[FUND_SEED, fund_name.as_bytes(), &[fund_bump]]]
+    )?;
+
+    // Deserialization and Serialization of Fund data
+    let fund_data = FundAccount {
+        version: FUND_ACCOUNT_VERSION,
+        name,
+        creator: *creator_wallet_info.key,
+        members: vec![*creator_wallet_info.key],
+        total_deposit: 0_u64,
+        governance_mint: *governance_mint_info.key,
+        vault: *vault_account_info.key,
+        is_initialized: true,
+        created_at: current_time,
+        is_private: privacy,
+        is_paused: false,
+        deposits_frozen: false,
+        admin_nonce: 0,
+        active_proposals: 0,
+        last_proposal_at: 0,
+        config,
+        accepted_mints,
+    };
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    // Deserializing the User Global PDA
+    assert_owned_by(user_account_info, program_id)?;
+    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
+
+    // Check is User is already in the Fund (vague since fund account is just created)
+    if user_data.funds.contains(fund_account_info.key) {
+        msg!("User is already a member");
+        return Ok(())
+    }
+
+    // Calculate current size and new size of User PDA
+    let current_size = user_account_info.data_len();
+    let new_size = current_size + 32;
+
+    // Calculate current min rent-exempt and new rent-exempt
+    let new_min_balance = rent.minimum_balance(new_size);
+    let current_balance = user_account_info.lamports();
+
+    // Deposit lamports if required
+    if new_min_balance > current_balance {
+        invoke(
+            &system_instruction::transfer(
+                creator_wallet_info.key,
+                user_account_info.key,
+                new_min_balance - current_balance,
+            ),
+            &[creator_wallet_info.clone(), user_account_info.clone(), system_program_info.clone()],
+        )?;
+    }
+
+    // Reallocation for new bytes
+    user_account_info.realloc(new_size, false)?;
+    // Add the new fund address to user funds
+    user_data.funds.push(*fund_account_info.key);
+    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+
+    // Deserialization and Serialization of Vault Account Data
+    let vault_data = VaultAccount {
+        version: VAULT_ACCOUNT_VERSION,
+        fund: *fund_account_info.key,
+        last_deposit_time: 0,
+        cached_value: 0,
+        last_valuation_time: 0,
+    };
+    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+    msg!("Fund Initialization successful");
+
+    create_user_specific_pda(
+        program_id,
+        creator_wallet_info,
+        system_program_info,
+        fund_account_info,
+        user_specific_info,
+        ROLE_ALL
+    )
+}
+
+fn process_init_user_account<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    user_seed: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // User Wallet
+    let user_account_info = next_account_info(accounts_iter)?; // User PDA Account to be created
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let user_specific_info = next_account_info(accounts_iter)?; // User-specific PDA to be created
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    // User should be the signer
+    if !creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Global User PDA is shared by every Fund the user takes part in, create it on first use
+    if user_account_info.data_is_empty() {
+        create_user_pda(program_id, creator_account_info, user_account_info, system_program_info)?;
+        msg!("User Account created successfully");
+    }
+
+    // Fund must exist for the user to have an account in it
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, user_seed.as_bytes());
+    if *fund_account_info.key != fund_pda || fund_account_info.data_is_empty() {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+
+    if !user_specific_info.data_is_empty() {
+        msg!("User already exists");
+        return Ok(());
+    }
+
+    // Stays inactive until the user joins the Fund
+    create_user_specific_account(program_id, creator_account_info, system_program_info, fund_account_info, user_specific_info)?;
+    msg!("User-specific Account created successfully");
+
+    Ok(())
+}
+
+// Creates the global User PDA of `creator`, listing no Funds yet
+fn create_user_pda<'a>(
+    program_id: &Pubkey,
+    creator_account_info: &AccountInfo<'a>,
+    user_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    // Derive User PDA and check if provided is same as expected
+    let (user_pda, user_bump) = derive_user_pda(program_id, creator_account_info.key);
+    if *user_account_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Calculate rent-exempt
+    let rent = Rent::get()?;
+    let user_space = 32 + 4_usize;
+    
+    // Create the User PDA Account which is it's global identity
+    invoke_signed(
+        &system_instruction::create_account(
+            creator_account_info.key,
+            user_account_info.key,
+            rent.minimum_balance(user_space),
+            user_space as u64,
+            program_id
+        ),
+        &[creator_account_info.clone(), user_account_info.clone(), system_program_info.clone()],
+        &[&[USER_SEED, creator_account_info.key.as_ref(), &[user_bump]]]
+    )?;
+
+    // Initiallt user is joined in no Funds
+    let funds:Vec<Pubkey> = vec![];
+
+    // Deserialization and Serialization of User Account Data
+    let user_data = UserAccount {
+        user: *creator_account_info.key,
+        funds,
+    };
+    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn process_register_fund_membership(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Member Wallet, pays for the User PDA
+    let user_account_info = next_account_info(accounts_iter)?; // Member's User PDA
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Only Funds the wallet is actually a member of can be listed
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if !fund_data.members.contains(member_account_info.key) {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    // Global User PDA is created on first registration
+    if user_account_info.data_is_empty() {
+        create_user_pda(program_id, member_account_info, user_account_info, system_program_info)?;
+    }
+    let (user_pda, _user_bump) = derive_user_pda(program_id, member_account_info.key);
+    if *user_account_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(user_account_info, program_id)?;
+
+    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
+    if user_data.funds.contains(&fund_pda) {
+        msg!("Fund already registered");
+        return Ok(());
+    }
+
+    grow_account(user_account_info, member_account_info, system_program_info, 32)?;
+    user_data.funds.push(fund_pda);
+    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn process_add_member<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let member_account_info = next_account_info(accounts_iter)?; // USer to be added 
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let user_account_info = next_account_info(accounts_iter)?; // User Global identity account
+    let user_specific_info = next_account_info(accounts_iter)?; // User Specific pda
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    // User should be signer
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Derive PDAs and check if it is same as provided in accounts
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_pda(program_id, member_account_info.key);
+    if *fund_account_info.key != fund_pda || *user_account_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Check if Fund exist or not!
+    if fund_account_info.data_is_empty() {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Deserialize the fund data. Anyone can join a public Fund while there is room, private
+    // Funds only take members admitted by an add-member proposal.
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if !fund_data.members.contains(member_account_info.key) {
+        if fund_data.is_private != 0 {
+            return Err(FundError::PrivateFund.into());
+        }
+        if fund_data.members.len() >= MAX_FUND_MEMBERS {
+            return Err(FundError::FundFull.into());
+        }
+    }
+
+    // Deserialize User Data and check if User is already a member of provided Fund
+    assert_owned_by(user_account_info, program_id)?;
+    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
+    if user_data.funds.contains(fund_account_info.key) {
+        msg!("User is already a member");
+        return Ok(());
+    }
+
+    // Calculate if more lamports are needed for reallocation of User Global Account
+    let current_size = user_account_info.data_len();
+    let new_size = current_size + 32;
+    let rent = Rent::get()?;
+    let new_min_balance = rent.minimum_balance(new_size);
+    let current_balance = user_account_info.lamports();
+    if new_min_balance > current_balance {
+        invoke(
+            &system_instruction::transfer(
+                member_account_info.key,
+                user_account_info.key,
+                new_min_balance - current_balance,
+            ),
+            &[member_account_info.clone(), user_account_info.clone(), system_program_info.clone()],
+        )?;
+    }
+
+    // invoke(
+    //     &system_instruction::transfer(
+    //         member_account_info.key,
+    //         user_account_info.key,
+    //         400_000_000,
+    //     ),
+    //     &[member_account_info.clone(), user_account_info.clone(), system_program_info.clone()]
+    // )?;
+
+    // Reallocate new bytes ofr storage of new Fund addresss
+    user_account_info.realloc(new_size, false)?;
+    user_data.funds.push(*fund_account_info.key);
+    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+
+    if fund_data.members.contains(member_account_info.key) {
+        // Member was already admitted (e.g. by an add-member proposal), only their accounts are missing
+        return create_user_specific_pda(
+            program_id,
+            member_account_info,
+            system_program_info,
+            fund_account_info,
+            user_specific_info,
+            ROLE_PROPOSER
+        );
+    }
+
+    // New member's share of the rent fee, split among existing members.
+    // Integer-division remainder goes to the first member so the full share is paid out.
+    let n = fund_data.members.len() as u64;
+    let share = 4593600_u64/(n+1);
+    let refund = share/n;
+    let remainder = share%n;
+    // Refund to existing members to equally distribute the rent fee
+    for i in 0..fund_data.members.len() {
+        let receiver_account_info = next_account_info(accounts_iter)?;
+        if *receiver_account_info.key != fund_data.members[i] {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        let amount = if i == 0 { refund + remainder } else { refund };
+        invoke(
+            &system_instruction::transfer(
+                member_account_info.key,
+                receiver_account_info.key,
+                amount,
+            ),
+            &[member_account_info.clone(), receiver_account_info.clone(), system_program_info.clone()],
+        )?;
+    }
+
+    // Grow the Fund account by one member slot, new member pays the extra rent
+    grow_account(fund_account_info, member_account_info, system_program_info, 32)?;
+
+    fund_data.members.push(*member_account_info.key);
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    create_user_specific_pda(
+        program_id,
+        member_account_info,
+        system_program_info,
+        fund_account_info,
+        user_specific_info,
+        ROLE_PROPOSER
+    )
+    // Ok(())
+
+}
+
+// fn process_init_deposit_sol(
+//     program_id: &Pubkey,
+//     accounts: &[AccountInfo],
+//     amount: u64,
+//     fund_name: String,
+// ) -> ProgramResult {
+//     let current_time = Clock::get()?.unix_timestamp;
+
+//     let accounts_iter = &mut accounts.iter();
+//     let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+//     let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+//     let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+//     let system_program_info = next_account_info(accounts_iter)?; // System Program
+//     let token_program_info = next_account_info(accounts_iter)?; // Token Program
+//     let governance_token_account_info = next_account_info(accounts_iter)?; // Governance token Account of depositor
+//     let member_account_info = next_account_info(accounts_iter)?; // Depositor Wallet
+//     let user_specific_pda_info = next_account_info(accounts_iter)?; // Depositor's Fund Specific Account
+//     let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar Account
+//     let associated_token_program_info = next_account_info(accounts_iter)?;
+
+//     // Depositor needs to be signer
+//     if !member_account_info.is_signer {
+//         msg!("Required Signer not found");
+//         return Err(FundError::MissingRequiredSignature.into());
+//     }
+
+//     // Derive PDAs and check for equality with provided ones
+//     let (governance_mint, _governance_bump) = Pubkey::find_program_address(&[b"governance", fund_account_info.key.as_ref()], program_id);
+//     let (fund_pda, fund_bump) = Pubkey::find_program_address(&[b"fund", fund_name.as_bytes()], program_id);
+//     let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_account_info.key.as_ref()], program_id);
+//     let (user_specific_pda, user_specific_bump) = Pubkey::find_program_address(&[b"user", fund_account_info.key.as_ref(), member_account_info.key.as_ref()], program_id);
+//     if *fund_account_info.key != fund_pda ||
+//        *vault_account_info.key != vault_pda ||
+//        *user_specific_pda_info.key != user_specific_pda ||
+//        *governance_mint_info.key != governance_mint {
+//         return Err(FundError::InvalidAccountData.into());
+//     }
+//     let expected_ata = spl_associated_token_account::get_associated_token_address(
+//         member_account_info.key,
+//         governance_mint_info.key,
+//     );
+//     if *governance_token_account_info.key != expected_ata {
+//         return Err(FundError::InvalidTokenAccount.into());
+//     }
+
+//     // If depositor's token account doesn't exist for governance mint, then create it
+//     if governance_token_account_info.data_is_empty() {
+//         invoke(
+//             &spl_associated_token_account::instruction::create_associated_token_account(
+//                 member_account_info.key,
+//                 member_account_info.key,
+//                 governance_mint_info.key,
+//                 token_program_info.key,
+//             ),
+//             &[
+//                 member_account_info.clone(),
+//                 governance_token_account_info.clone(),
+//                 member_account_info.clone(),
+//                 governance_mint_info.clone(),
+//                 token_program_info.clone(),
+//                 system_program_info.clone(),
+//                 associated_token_program_info.clone(),
+//                 rent_sysvar_info.clone(),
+//             ]
+//         )?;
+//     }
+
+//     // Deposit required SOL from depositor to vault account
+//     invoke(
+//         &system_instruction::transfer(
+//             member_account_info.key,
+//             vault_account_info.key,
+//             amount,
+//         ),
+//         &[
+//             member_account_info.clone(),
+//             vault_account_info.clone(),
+//             system_program_info.clone(),
+//         ]
+//     )?;
+
+//     // Now mint equal amount of governance tokens to depositor's governance token account
+//     invoke_signed(
+//         &spl_token::instruction::mint_to(
+//             token_program_info.key,
+//             governance_mint_info.key,
+//             governance_token_account_info.key,
+//             fund_account_info.key,
+//             &[],
+//             amount,
+//         )?,
+//         &[
+//             governance_mint_info.clone(),
+//             governance_token_account_info.clone(),
+//             fund_account_info.clone(),
+//             token_program_info.clone(),
+//             // rent_sysvar_info.clone(),
+//         ],
+//         &[&[b"fund", fund_name.as_bytes(), &[fund_bump]]],
+//     )?;
+
+//     // In vault account, set the last deposit time
+//     let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+//     vault_data.last_deposit_time = current_time;
+//     vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+
+//     // In fund account increase the deposited amount (unit lamports)
+//     let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+//     fund_data.total_deposit += amount;
+//     fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+//     Ok(())
+// }
+
+fn process_init_deposit_token<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Depositor wallet
+    let member_ata_info = next_account_info(accounts_iter)?; // Depositor's ATA for the depositing mint
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let vault_ata_info = next_account_info(accounts_iter)?; // Vault PDA's ATA for the depositing mint
+    let mint_account_info = next_account_info(accounts_iter)?; // Mint account of token to be deposited
+    let token_program_info = next_account_info(accounts_iter)?; // Token Progarm
+    let ata_program_info = next_account_info(accounts_iter)?; // Associated Token Program
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // USER Specific PDA
+    let system_program_info = next_account_info(accounts_iter)?; // System program
+    let rent_sysvar_info = next_account_info(accounts_iter)?; // Rent Sysvar Account
+    let governance_token_account_info = next_account_info(accounts_iter)?; // Governance Token Account of depositor
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+
+    // Programs invoked below must be the canonical ones
+    assert_program(token_program_info, &spl_token::id())?;
+    assert_program(ata_program_info, &spl_associated_token_account::id())?;
+    assert_program(system_program_info, &system_program::id())?;
+    // let temp_wsol_account_info = next_account_info(accounts_iter)?;
+    // Fee Collector's Token Account for the depositing mint follows when the Fund charges a deposit fee
+
+    // Depositor should be signer
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Fund, Vault and User-specific PDA are all rewritten below
+    assert_writable(fund_account_info)?;
+    assert_writable(vault_account_info)?;
+    assert_writable(user_specific_pda_info)?;
+
+    // Derive the PDAs and check for equality with provided ones
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_account_info.key.as_ref()], program_id);
+    let (fund_pda, fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_account_info.key);
+    if *vault_account_info.key != vault_pda || *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_specific_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let expected_ata = spl_associated_token_account::get_associated_token_address(
+        member_account_info.key,
+        governance_mint_info.key,
+    );
+    if *governance_token_account_info.key != expected_ata {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    // Governance mint must be the one whose authority is this Fund PDA
+    let (governance_mint, _governance_bump) = derive_governance_mint_pda(program_id, &fund_pda);
+    if *governance_mint_info.key != governance_mint {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+
+    // Deposit lands in the Vault's own ATA for the mint, anything else could be a depositor's
+    // account whose balance would then be read as the Vault's
+    let vault_ata = spl_associated_token_account::get_associated_token_address(&vault_pda, mint_account_info.key);
+    if *vault_ata_info.key != vault_ata {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    if !vault_ata_info.data_is_empty() {
+        assert_owned_by(vault_ata_info, &spl_token::id())?;
+        if TokenAccount::unpack(&vault_ata_info.data.borrow())?.owner != vault_pda {
+            return Err(FundError::InvalidTokenAccount.into());
+        }
+    }
+
+    // Only members of the Fund can deposit and receive governance tokens
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+    if fund_data.deposits_frozen {
+        return Err(FundError::DepositsFrozen.into());
+    }
+    // Depositor's Fund-specific account is created by InitUserAccount or when joining, it must exist
+    // before depositing
+    if user_specific_pda_info.data_is_empty() || user_specific_pda_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    if !fund_data.members.contains(member_account_info.key) {
+        // Private Funds only admit members through proposals, public ones let the first deposit join
+        if fund_data.is_private != 0 {
+            return Err(FundError::NotAFundMember.into());
+        }
+        if fund_data.members.len() >= MAX_FUND_MEMBERS {
+            return Err(FundError::FundFull.into());
+        }
+
+        // Grow the Fund account by one member slot, joining depositor pays the extra rent
+        grow_account(fund_account_info, member_account_info, system_program_info, 32)?;
+
+        fund_data.members.push(*member_account_info.key);
+        fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+        create_user_specific_pda(
+            program_id,
+            member_account_info,
+            system_program_info,
+            fund_account_info,
+            user_specific_pda_info,
+            ROLE_PROPOSER
+        )?;
+    }
+
+    // Deposited mint must be on the Fund's allowlist
+    let Some(accepted_mint) = fund_data.accepted_mint(mint_account_info.key).copied() else {
+        return Err(FundError::MintNotAccepted.into());
+    };
+
+    // Fund's deposit fee is cut from the amount, only the rest is credited to the depositor
+    let fee = ((amount as u128) * (fund_data.config.deposit_fee_bps as u128) / 10000) as u64;
+    let net_amount = amount - fee;
+    let fee_account_info = if fee > 0 {
+        let fee_account_info = next_account_info(accounts_iter)?;
+        assert_owned_by(fee_account_info, &spl_token::id())?;
+        let fee_account_data = TokenAccount::unpack(&fee_account_info.data.borrow())?;
+        if fee_account_data.owner != fund_data.config.fee_collector || fee_account_data.mint != *mint_account_info.key {
+            return Err(FundError::InvalidTokenAccount.into());
+        }
+        Some(fee_account_info)
+    } else {
+        None
+    };
+
+    // Deposits are credited in lamports. WSOL deposits pass the Fund's reserve account after the fee
+    // account, other mints are priced through their bound Pyth feed against SOL/USD, both passed
+    // after the fee account instead.
+    let mut reserve_info = None;
+    let deposit_value = if *mint_account_info.key == spl_token::native_mint::id() {
+        reserve_info = Some(next_account_info(accounts_iter)?); // Fund's Reserve WSOL Account
+        net_amount
+    } else {
+        let sol_feed_info = next_account_info(accounts_iter)?; // Pyth SOL/USD price feed
+        let price_feed_info = next_account_info(accounts_iter)?; // Deposited mint's Pyth price feed
+        if *sol_feed_info.key != SOL_USD_PRICE_FEED || *price_feed_info.key != accepted_mint.price_feed {
+            return Err(FundError::InvalidOracleAccount.into());
+        }
+        let sol_price = read_price(sol_feed_info, fund_data.config.max_price_age)?;
+        let token_price = read_price(price_feed_info, fund_data.config.max_price_age)?;
+        assert_owned_by(mint_account_info, &spl_token::id())?;
+        let decimals = spl_token::state::Mint::unpack(&mint_account_info.data.borrow())?.decimals;
+        token_value_in_lamports(net_amount, decimals, token_price, sol_price)?
+    };
+
+    // Deposit must stay within the Fund's and the member's caps
+    let user_specific_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_specific_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    let user_deposit = user_specific_data.deposit;
+    let max_total_deposit = fund_data.config.max_total_deposit;
+    let max_member_deposit = fund_data.config.max_member_deposit;
+    if (max_total_deposit != 0 && fund_data.total_deposit.saturating_add(deposit_value) > max_total_deposit) ||
+       (max_member_deposit != 0 && user_deposit.saturating_add(deposit_value) > max_member_deposit) {
+        return Err(FundError::DepositCapExceeded.into());
+    }
+
+    // If depositor's governance token account doesn't exist, create one
+    if governance_token_account_info.data_is_empty() {
+        invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account(
+                member_account_info.key,
+                member_account_info.key,
+                governance_mint_info.key,
+                token_program_info.key,
+            ),
+            &[
+                member_account_info.clone(),
+                governance_token_account_info.clone(),
+                governance_mint_info.clone(),
+                system_program_info.clone(),
+                token_program_info.clone(),
+                ata_program_info.clone(),
+                rent_sysvar_info.clone(),
+            ]
+        )?;
+    }
+
+    let rent =Rent::get()?;
+    let rent_req = rent.minimum_balance(TokenAccount::LEN);
+
+    // If vault's token account account for the depositing mint doesn't exist, create it
+    if vault_ata_info.data_is_empty() {
+        msg!("Creating Vault ATA...");
+
+        invoke(
+            &create_associated_token_account(
+                member_account_info.key,
+                &vault_pda,
+                mint_account_info.key,
+                token_program_info.key
+            ),
+            &[
+                member_account_info.clone(),
+                vault_ata_info.clone(),
+                vault_account_info.clone(),
+                mint_account_info.clone(),
+                system_program_info.clone(),
+                token_program_info.clone(),
+                ata_program_info.clone(),
+                rent_sysvar_info.clone(),
+            ],
+        )?;
+    }
+
+    // Deposits buy governance tokens at the vault's current share price. Vault value is the
+    // cached valuation when fresh. Only a Fund accepting nothing but WSOL may fall back to its
+    // WSOL balance before this deposit, any other has holdings that need a RefreshVaultValue.
+    assert_owned_by(vault_account_info, program_id)?;
+    let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+    let cached_vault_value = fresh_vault_value(&vault_data, current_time);
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let governance_supply = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?.supply;
+    let vault_value = match cached_vault_value {
+        Some(value) => value,
+        None if fund_data.accepts_only_wsol() => {
+            assert_owned_by(vault_ata_info, &spl_token::id())?;
+            let reserve_balance = match reserve_info {
+                Some(reserve_info) => reserve_wsol_amount(program_id, &fund_pda, reserve_info)?,
+                None => 0,
+            };
+            TokenAccount::unpack(&vault_ata_info.data.borrow())?.amount
+                .checked_add(reserve_balance)
+                .ok_or(FundError::ArithmeticOverflow)?
+        }
+        None => return Err(FundError::StaleVaultValue.into()),
+    };
+    let governance_tokens = governance_tokens_for_deposit(deposit_value, governance_supply, vault_value)?;
+    // A dust first deposit, or one the share price rounds down to no tokens, would hand the
+    // depositor's value to the existing holders
+    if (governance_supply == 0 && deposit_value < MIN_FIRST_DEPOSIT) || governance_tokens == 0 {
+        return Err(FundError::DepositTooSmall.into());
+    }
+
+    // Only WSOL deposits carry the reserve account
+    if let Some(reserve_info) = reserve_info {
+        // Wrap SOL: create the depositor's WSOL account on first deposit, top it up on later ones
+        if member_ata_info.data_is_empty() {
+            invoke(
+                &system_instruction::create_account(
+                    member_account_info.key,
+                    member_ata_info.key,
+                    rent_req.checked_add(amount).ok_or(FundError::ArithmeticOverflow)?,
+                    TokenAccount::LEN as u64,
+                    token_program_info.key,
+                ),
+                &[
+                    member_account_info.clone(),
+                    member_ata_info.clone(),
+                    token_program_info.clone(),
+                    system_program_info.clone(),
+                ]
+            )?;
+
+            invoke(
+                &spl_token::instruction::initialize_account(
+                    token_program_info.key,
+                    member_ata_info.key,
+                    mint_account_info.key,
+                    member_account_info.key,
+                )?,
+                &[
+                    token_program_info.clone(),
+                    member_ata_info.clone(),
+                    mint_account_info.clone(),
+                    member_account_info.clone(),
+                ]
+            )?;
+        } else {
+            invoke(
+                &system_instruction::transfer(
+                    member_account_info.key,
+                    member_ata_info.key,
+                    amount,
+                ),
+                &[
+                    member_account_info.clone(),
+                    member_ata_info.clone(),
+                    system_program_info.clone(),
+                ]
+            )?;
+
+            invoke(
+                &spl_token::instruction::sync_native(
+                    token_program_info.key,
+                    member_ata_info.key,
+                )?,
+                &[
+                    member_ata_info.clone(),
+                    token_program_info.clone(),
+                ]
+            )?;
+        }
+
+        // Fund's reserve share goes to its reserve account, the rest to the vault
+        let reserved = (net_amount as u128 * fund_data.config.reserve_bps as u128 / 10000) as u64;
+        if reserved > 0 {
+            if reserve_info.data_is_empty() {
+                create_reserve_account(
+                    program_id,
+                    member_account_info,
+                    reserve_info,
+                    &vault_pda,
+                    &fund_pda,
+                    mint_account_info,
+                    system_program_info,
+                    token_program_info,
+                )?;
+            }
+            // Reserve must be the Fund's own WSOL account
+            reserve_wsol_amount(program_id, &fund_pda, reserve_info)?;
+
+            invoke(
+                &spl_token::instruction::transfer(
+                    token_program_info.key,
+                    member_ata_info.key,
+                    reserve_info.key,
+                    member_account_info.key,
+                    &[],
+                    reserved
+                )?,
+                &[
+                    token_program_info.clone(),
+                    member_ata_info.clone(),
+                    reserve_info.clone(),
+                    member_account_info.clone(),
+                ]
+            )?;
+        }
+
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                member_ata_info.key,
+                vault_ata_info.key,
+                member_account_info.key,
+                &[],
+                net_amount - reserved
+            )?,
+            &[
+                token_program_info.clone(),
+                member_ata_info.clone(),
+                vault_ata_info.clone(),
+                member_account_info.clone(),
+            ]
+        )?;
+    } else {
+        msg!("Transferring tokens...");
+        // Now transfer the required number of tokens from depositor's token account to vault's token account
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                member_ata_info.key,
+                vault_ata_info.key,
+                member_account_info.key,
+                &[],
+                net_amount,
+            )?,
+            &[
+                member_ata_info.clone(),
+                vault_ata_info.clone(),
+                member_account_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[]
+        )?;
+    }
+
+    // Pay the Fund's deposit fee to the fee collector
+    if let Some(fee_account_info) = fee_account_info {
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                member_ata_info.key,
+                fee_account_info.key,
+                member_account_info.key,
+                &[],
+                fee,
+            )?,
+            &[
+                member_ata_info.clone(),
+                fee_account_info.clone(),
+                member_account_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+    }
+
+
+    // Now mint the governance tokens bought by the deposit to the depositor's governance token account
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            token_program_info.key,
+            governance_mint_info.key,
+            governance_token_account_info.key,
+            fund_account_info.key,
+            &[],
+            governance_tokens,
+        )?,
+        &[
+            governance_mint_info.clone(),
+            governance_token_account_info.clone(),
+            fund_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[FUND_SEED, fund_name.as_bytes(), &[fund_bump]]],
+    )?;
+
+    // In vault account, set the last deposit time and keep a fresh valuation in step with the deposit
+    vault_data.last_deposit_time = current_time;
+    if cached_vault_value.is_some() {
+        vault_data.cached_value = vault_data.cached_value.saturating_add(deposit_value);
+    }
+    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+    // In fund account increase the deposited amount (unit lamports)
+    fund_data.total_deposit = fund_data.total_deposit.checked_add(deposit_value).ok_or(FundError::ArithmeticOverflow)?;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    // In depositor's fund-specific account record the deposit and the governance tokens received
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    user_data.deposit = user_data.deposit.checked_add(deposit_value).ok_or(FundError::ArithmeticOverflow)?;
+    // Stake for proposals created before this deposit. Ones from before the previous deposit keep
+    // theirs, until MAX_PROPOSAL_WINDOW has passed and they can't take votes anymore. Ones created
+    // since can't count more than the balance before this deposit.
+    let earlier_stake = if current_time.saturating_sub(user_data.last_deposit_time) < MAX_PROPOSAL_WINDOW {
+        user_data.pre_deposit_balance
+    } else {
+        u64::MAX
+    };
+    user_data.pre_deposit_balance = if fund_data.last_proposal_at != 0 && fund_data.last_proposal_at >= user_data.last_deposit_time {
+        earlier_stake.min(user_data.governance_token_balance)
+    } else {
+        earlier_stake
+    };
+    user_data.governance_token_balance = user_data.governance_token_balance.checked_add(governance_tokens).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.last_deposit_time = current_time;
+    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
+
+    events::emit_deposit(fund_account_info.key, member_account_info.key, net_amount);
+
+    Ok(())
+}
+
+fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+    nonce: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // Creator Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+
+    // Creator should be signer
+    if !creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Only the Fund's creator can trip or reset the circuit breaker
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.creator != *creator_account_info.key {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    consume_admin_nonce(&mut fund_data, nonce)?;
+    fund_data.is_paused = paused;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    msg!("Fund paused: {}", paused);
+
+    Ok(())
+}
+
+fn process_freeze_deposits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    frozen: bool,
+    nonce: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // Creator Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+
+    // Creator should be signer
+    if !creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Only the Fund's creator can close or reopen it to deposits. Unlike handing off the mint
+    // authority this is reversible, and withdrawals and governance keep working.
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.creator != *creator_account_info.key {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    consume_admin_nonce(&mut fund_data, nonce)?;
+    fund_data.deposits_frozen = frozen;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    msg!("Fund deposits frozen: {}", frozen);
+
+    Ok(())
+}
+
+fn process_transfer_creator(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_creator: Pubkey,
+    nonce: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // Creator Wallet
+    let new_creator_account_info = next_account_info(accounts_iter)?; // New Creator Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+
+    // Both sides sign, so the creator's powers can't be handed to a key nobody controls
+    if !creator_account_info.is_signer || !new_creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+    if *new_creator_account_info.key != new_creator {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.creator != *creator_account_info.key {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+    if !fund_data.members.contains(&new_creator) {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    consume_admin_nonce(&mut fund_data, nonce)?;
+    fund_data.creator = new_creator;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    msg!("Fund creator transferred to {}", new_creator);
+
+    Ok(())
+}
+
+fn process_refresh_vault_value(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // Creator Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault PDA's WSOL ATA
+    let reserve_info = next_account_info(accounts_iter)?; // Fund's Reserve WSOL Account
+
+    // Creator should be signer
+    if !creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(
+        &vault_pda,
+        &spl_token::native_mint::id(),
+    );
+    if *vault_wsol_info.key != expected_vault_wsol {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    assert_writable(vault_account_info)?;
+
+    // Only the creator refreshes, since the valuation rests on the holdings and price feeds they pass
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.creator != *creator_account_info.key {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Vault value is its WSOL balance and reserve plus the token holdings passed after them
+    assert_owned_by(vault_wsol_info, &spl_token::id())?;
+    let vault_wsol_balance = TokenAccount::unpack(&vault_wsol_info.data.borrow())?.amount;
+    let vault_value = vault_wsol_balance
+        .checked_add(reserve_wsol_amount(program_id, &fund_pda, reserve_info)?)
+        .ok_or(FundError::ArithmeticOverflow)?
+        .checked_add(value_token_holdings(&fund_data, &vault_pda, accounts_iter)?)
+        .ok_or(FundError::ArithmeticOverflow)?;
+
+    assert_owned_by(vault_account_info, program_id)?;
+    let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+    vault_data.cached_value = vault_value;
+    vault_data.last_valuation_time = current_time;
+    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+    msg!("Vault value: {}", vault_value);
+
+    Ok(())
+}
+
+fn process_get_fund_valuation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault PDA's WSOL ATA
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let reserve_info = next_account_info(accounts_iter)?; // Fund's Reserve WSOL Account
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(
+        &vault_pda,
+        &spl_token::native_mint::id(),
+    );
+    if *vault_wsol_info.key != expected_vault_wsol {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+
+    // Read-only: supply and vault value from the same instruction, so they describe one moment.
+    // Holdings are valued the same way RefreshVaultValue values them.
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let governance_mint = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?;
+    assert_owned_by(vault_wsol_info, &spl_token::id())?;
+    let vault_lamports = TokenAccount::unpack(&vault_wsol_info.data.borrow())?.amount
+        .checked_add(reserve_wsol_amount(program_id, &fund_pda, reserve_info)?)
+        .ok_or(FundError::ArithmeticOverflow)?;
+    let vault_token_values = value_token_holdings(&fund_data, &vault_pda, accounts_iter)?;
+
+    // Lamports backing one whole governance token
+    let total_value = vault_lamports as u128 + vault_token_values as u128;
+    let price_per_token = if governance_mint.supply == 0 {
+        0
+    } else {
+        u64::try_from(total_value * 10u128.pow(governance_mint.decimals as u32) / governance_mint.supply as u128)
+            .map_err(|_| FundError::ArithmeticOverflow)?
+    };
+
+    let valuation = FundValuation {
+        supply: governance_mint.supply,
+        vault_lamports,
+        vault_token_values,
+        price_per_token,
+    };
+    set_return_data(&valuation.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_migrate_fund<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer_account_info = next_account_info(accounts_iter)?; // Payer Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    // Payer should be signer
+    if !payer_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+    assert_owned_by(vault_account_info, program_id)?;
+
+    // Accounts already at the current layout parse as such, anything else must be a version 1 Fund
+    if let Ok(fund_data) = FundAccount::try_from_slice(&fund_account_info.data.borrow()) {
+        if fund_data.version == FUND_ACCOUNT_VERSION {
+            return Err(FundError::AlreadyMigrated.into());
+        }
+    }
+    let old_data = FundAccountV1::try_from_slice(&fund_account_info.data.borrow())?;
+    if !old_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if old_data.members > MAX_FUND_MEMBERS as u64 {
+        return Err(FundError::InvalidMemberCount.into());
+    }
+
+    // Version 1 only counted members. Remaining accounts are (Member's Wallet, User-specific PDA)
+    // for every active member, each migrated along the way, and together they become the list.
+    let mut members: Vec<Pubkey> = Vec::new();
+    while let Some(member_wallet_info) = accounts_iter.next() {
+        let user_specific_info = next_account_info(accounts_iter)?; // Member's User-specific PDA
+
+        let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_wallet_info.key);
+        if *user_specific_info.key != user_specific_pda || members.contains(member_wallet_info.key) {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        assert_owned_by(user_specific_info, program_id)?;
+        let old_user_data = UserSpecificAccountV1::try_from_slice(&user_specific_info.data.borrow())?;
+        if old_user_data.fund != fund_pda || old_user_data.pubkey != *member_wallet_info.key || !old_user_data.is_active {
+            return Err(FundError::NotAFundMember.into());
+        }
+
+        let roles = if *member_wallet_info.key == old_data.creator { ROLE_ALL } else { ROLE_PROPOSER };
+        let user_data = UserSpecificAccount {
+            version: USER_SPECIFIC_ACCOUNT_VERSION,
+            pubkey: old_user_data.pubkey,
+            fund: old_user_data.fund,
+            deposit: old_user_data.deposit,
+            governance_token_balance: old_user_data.governance_token_balance,
+            is_active: true,
+            num_proposals: old_user_data.num_proposals,
+            join_time: old_user_data.join_time,
+            last_deposit_time: old_user_data.join_time,
+            delegate: Pubkey::default(),
+            is_initialized: true,
+            roles,
+            open_proposals: 0,
+            pre_deposit_balance: u64::MAX,
+        };
+        grow_account(user_specific_info, payer_account_info, system_program_info, UserSpecificAccount::LEN - user_specific_info.data_len())?;
+        user_data.serialize(&mut &mut user_specific_info.data.borrow_mut()[..])?;
+
+        members.push(*member_wallet_info.key);
+    }
+    if members.len() as u64 != old_data.members {
+        return Err(FundError::InvalidMemberCount.into());
+    }
+
+    // Version 1 had no governance settings, the migrated Fund keeps its simple-majority voting and
+    // WSOL-only deposits. Its governance mint was created with 0 decimals.
+    let config = FundConfig {
+        quorum_bps: 0,
+        pass_threshold_bps: 5000,
+        governance_decimals: 0,
+        max_total_deposit: 0,
+        max_member_deposit: 0,
+        lockup_seconds: 0,
+        min_proposal_deposit: 0,
+        deposit_fee_bps: 0,
+        fee_collector: Pubkey::default(),
+        proposal_bond: 0,
+        voting_mode: VOTING_MODE_TOKEN_WEIGHTED,
+        time_weight_cap_seconds: 0,
+        dex_allowlist: 0,
+        min_voting_period: DEFAULT_MIN_VOTING_PERIOD,
+        anti_snipe_window: 0,
+        deadline_extension: 0,
+        execution_reward: 0,
+        execution_delay: 0,
+        reserve_bps: 0,
+        max_open_proposals_per_member: 0,
+        max_price_age: DEFAULT_MAX_PRICE_AGE,
+    };
+    let fund_data = FundAccount {
+        version: FUND_ACCOUNT_VERSION,
+        name: old_data.name,
+        creator: old_data.creator,
+        members,
+        total_deposit: old_data.total_deposit,
+        governance_mint: old_data.governance_mint,
+        vault: old_data.vault,
+        is_initialized: old_data.is_initialized,
+        created_at: old_data.created_at,
+        is_private: old_data.is_private,
+        is_paused: false,
+        deposits_frozen: false,
+        admin_nonce: 0,
+        active_proposals: 0,
+        last_proposal_at: 0,
+        config,
+        accepted_mints: vec![AcceptedMint { mint: spl_token::native_mint::id(), price_feed: SOL_USD_PRICE_FEED }],
+    };
+    let new_space = FundAccount::space(fund_data.members.len(), fund_data.accepted_mints.len());
+    grow_account(fund_account_info, payer_account_info, system_program_info, new_space - fund_account_info.data_len())?;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    // Vault gains the version byte and an empty valuation cache
+    let old_vault_data = VaultAccountV1::try_from_slice(&vault_account_info.data.borrow())?;
+    if old_vault_data.fund != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let vault_data = VaultAccount {
+        version: VAULT_ACCOUNT_VERSION,
+        fund: old_vault_data.fund,
+        last_deposit_time: old_vault_data.last_deposit_time,
+        cached_value: 0,
+        last_valuation_time: 0,
+    };
+    grow_account(vault_account_info, payer_account_info, system_program_info, VaultAccount::LEN - vault_account_info.data_len())?;
+    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+    msg!("Fund migrated to version {}", FUND_ACCOUNT_VERSION);
+
+    Ok(())
+}
+
+fn process_withdraw_sol(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Member wallet
+    let member_wsol_info = next_account_info(accounts_iter)?; // Member's WSOL token account to receive the withdrawal
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault PDA's WSOL ATA
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // User Specific PDA
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let governance_token_account_info = next_account_info(accounts_iter)?; // Governance Token Account of member
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+    let reserve_info = next_account_info(accounts_iter)?; // Fund's Reserve WSOL Account
+
+    // Programs invoked below must be the canonical ones
+    assert_program(token_program_info, &spl_token::id())?;
+
+    // Member should be signer
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Derive the PDAs and check for equality with provided ones
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_account_info.key);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda || *user_specific_pda_info.key != user_specific_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Governance mint must be the fund's, and token accounts must be the expected ATAs
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    let expected_governance_ata = spl_associated_token_account::get_associated_token_address(
+        member_account_info.key,
+        governance_mint_info.key,
+    );
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(
+        &vault_pda,
+        &spl_token::native_mint::id(),
+    );
+    if *governance_token_account_info.key != expected_governance_ata || *vault_wsol_info.key != expected_vault_wsol {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    // Receiving account must be a WSOL account owned by the member
+    assert_owned_by(member_wsol_info, &spl_token::id())?;
+    let member_wsol_data = TokenAccount::unpack(&member_wsol_info.data.borrow())?;
+    if member_wsol_data.mint != spl_token::native_mint::id() || member_wsol_data.owner != *member_account_info.key {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    // Member burns the governance tokens worth `amount` at the vault's current share price,
+    // and cannot withdraw more than their tokens are worth
+    assert_owned_by(user_specific_pda_info, program_id)?;
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let governance_supply = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?.supply;
+    assert_owned_by(vault_account_info, program_id)?;
+    let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+    let cached_vault_value = fresh_vault_value(&vault_data, current_time);
+    assert_owned_by(vault_wsol_info, &spl_token::id())?;
+    let vault_wsol_balance = TokenAccount::unpack(&vault_wsol_info.data.borrow())?.amount;
+    let reserve_balance = reserve_wsol_amount(program_id, &fund_pda, reserve_info)?;
+    let vault_sol = vault_wsol_balance.checked_add(reserve_balance).ok_or(FundError::ArithmeticOverflow)?;
+    let vault_value = match cached_vault_value {
+        Some(value) => value,
+        None if fund_data.accepts_only_wsol() => vault_sol,
+        None => return Err(FundError::StaleVaultValue.into()),
+    };
+    let governance_tokens = governance_tokens_for_withdrawal(amount, governance_supply, vault_value)?;
+    if amount > vault_sol || governance_tokens > user_data.governance_token_balance {
+        return Err(FundError::NotEnoughFunds.into());
+    }
+
+    // Latest deposit must have sat out the Fund's lockup period
+    if current_time < user_data.last_deposit_time.saturating_add(fund_data.config.lockup_seconds) {
+        return Err(FundError::LockupActive.into());
+    }
+
+    // Burn the governance tokens backing the withdrawn share
+    invoke(
+        &spl_token::instruction::burn(
+            token_program_info.key,
+            governance_token_account_info.key,
+            governance_mint_info.key,
+            member_account_info.key,
+            &[],
+            governance_tokens,
+        )?,
+        &[
+            governance_token_account_info.clone(),
+            governance_mint_info.clone(),
+            member_account_info.clone(),
+            token_program_info.clone(),
+        ]
+    )?;
+
+    // Transfer WSOL back to member, from the reserve first, vault PDA signs as owner
+    pay_out_wsol(
+        vault_account_info,
+        reserve_info,
+        reserve_balance,
+        vault_wsol_info,
+        member_wsol_info,
+        token_program_info,
+        &[b"vault", fund_pda.as_ref(), &[vault_bump]],
+        amount,
+    )?;
+
+    // Update member's and fund's deposit records, withdrawals can exceed the deposited principal
+    // once the vault has appreciated
+    user_data.deposit = user_data.deposit.saturating_sub(amount);
+    user_data.governance_token_balance = user_data.governance_token_balance.checked_sub(governance_tokens).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
+
+    fund_data.total_deposit = fund_data.total_deposit.saturating_sub(amount);
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    if cached_vault_value.is_some() {
+        vault_data.cached_value = vault_data.cached_value.saturating_sub(amount);
+    }
+    vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+
+    msg!("Withdrawal successful");
+
+    Ok(())
+}
+
+fn process_donate_sol(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let donor_account_info = next_account_info(accounts_iter)?; // Donor wallet, anyone may donate
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault PDA's WSOL ATA
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund PDA
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+    assert_program(token_program_info, &spl_token::id())?;
+
+    // Donor should be signer
+    if !donor_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Derive the PDAs and check for equality with provided ones
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(
+        &vault_pda,
+        &spl_token::native_mint::id(),
+    );
+    if *vault_wsol_info.key != expected_vault_wsol || vault_wsol_info.data_is_empty() {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+
+    // Lamports go straight into the vault's WSOL account and are synced into its balance. No
+    // governance tokens are minted, so every existing token is backed by a larger share of the vault.
+    invoke(
+        &system_instruction::transfer(
+            donor_account_info.key,
+            vault_wsol_info.key,
+            amount,
+        ),
+        &[
+            donor_account_info.clone(),
+            vault_wsol_info.clone(),
+            system_program_info.clone(),
+        ]
+    )?;
+    invoke(
+        &spl_token::instruction::sync_native(
+            token_program_info.key,
+            vault_wsol_info.key,
+        )?,
+        &[
+            vault_wsol_info.clone(),
+            token_program_info.clone(),
+        ]
+    )?;
+
+    // In fund account increase the deposited amount (unit lamports)
+    fund_data.total_deposit = fund_data.total_deposit.checked_add(amount).ok_or(FundError::ArithmeticOverflow)?;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    assert_owned_by(vault_account_info, program_id)?;
+    let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+    if fresh_vault_value(&vault_data, Clock::get()?.unix_timestamp).is_some() {
+        vault_data.cached_value = vault_data.cached_value.saturating_add(amount);
+        vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+    }
+
+    events::emit_donation(fund_account_info.key, donor_account_info.key, amount);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_init_investment_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_kind: u8,
+    amounts: Vec<u64>,
+    min_out: Vec<u64>,
+    dex_tags: Vec<u8>,
+    deadline: i64,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // Proposer's Fund-specific Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let registry_account_info = next_account_info(accounts_iter)?; // Fund's Proposal Registry PDA
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    // Proposer needs to be signer
+    if !proposer_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Only proposals with asset legs are created here
+    if proposal_kind != PROPOSAL_KIND_INVESTMENT {
+        return Err(FundError::InvalidInstruction.into());
+    }
+
+    // Every leg must route through a DEX the program can call
+    if !dex_tags.iter().all(|dex_tag| is_supported_dex(*dex_tag)) {
+        return Err(FundError::UnsupportedDex.into());
+    }
+
+    // Derive PDAs and check for equality with the provided ones
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_specific_pda(program_id, &fund_pda, proposer_account_info.key);
+    assert_owned_by(user_specific_pda_info, program_id)?;
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Proposer must be a member, and their Fund-specific account must belong to this Fund
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+    if !fund_data.members.contains(proposer_account_info.key) || user_data.fund != fund_pda {
+        return Err(FundError::NotAFundMember.into());
+    }
+    if user_data.roles & required_role(proposal_kind) == 0 {
+        return Err(FundError::InsufficientPermissions.into());
+    }
+    if fund_data.config.max_open_proposals_per_member > 0 &&
+       user_data.open_proposals >= fund_data.config.max_open_proposals_per_member {
+        return Err(FundError::TooManyOpenProposals.into());
+    }
+
+    // Proposal must leave the Fund's voting window
+    validate_deadline(deadline, current_time, fund_data.config.min_voting_period)?;
+
+    // Fund governs which of the supported DEXes its legs may use
+    if !dex_tags.iter().all(|dex_tag| is_dex_allowed(fund_data.config.dex_allowlist, *dex_tag)) {
+        return Err(FundError::UnsupportedDex.into());
+    }
+
+    // Proposer must have at least the Fund's minimum deposit at stake
+    if user_data.deposit < fund_data.config.min_proposal_deposit {
+        return Err(FundError::InsufficientStake.into());
+    }
+
+    // Snapshot the governance supply, voting power is measured against it
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let snapshot_supply = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?.supply;
+
+    // Extract From Assets Mint, before anything is created
+    let from_assets_info : Vec<&AccountInfo> = accounts_iter
+        .take(amounts.len())
+        .collect();
+    if from_assets_info.len() != amounts.len() {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let from_assets_mints: Vec<Pubkey> = from_assets_info.iter().map(|m| *m.key).collect();
+
+    // Extract To Assets Mint
+    let to_assets_info: Vec<&AccountInfo> = accounts_iter
+        .take(amounts.len())
+        .collect();
+    if to_assets_info.len() != amounts.len() {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let to_assets_mints: Vec<Pubkey> = to_assets_info.iter().map(|m| *m.key).collect();
+
+    // Every asset must be a real SPL mint
+    for asset_info in from_assets_info.iter().chain(to_assets_info.iter()) {
+        assert_mint(asset_info)?;
+    }
+
+    // Vault may only buy mints it has a price feed for, so every holding can be valued
+    if to_assets_mints.iter().any(|mint| fund_data.accepted_mint(mint).is_none()) {
+        return Err(FundError::MintNotAccepted.into());
+    }
+
+    // Create Proposal Account, sized for its asset legs
+    let proposal_space = InvestmentProposalAccount::space(amounts.len());
+    create_proposal_pda(
+        program_id,
+        proposer_account_info,
+        fund_account_info,
+        proposal_account_info,
+        system_program_info,
+        user_data.num_proposals,
+        proposal_space,
+        fund_data.config.proposal_bond,
+    )?;
+
+    // Deserialization and Serialization of Proposal Account data
+    let proposal_data = InvestmentProposalAccount {
+        version: PROPOSAL_ACCOUNT_VERSION,
+        fund: *fund_account_info.key,
+        proposer: *proposer_account_info.key,
+        proposal_kind,
+        target_member: Pubkey::default(),
+        recipient: Pubkey::default(),
+        lamports: 0,
+        config_change: ConfigChange::default(),
+        from_assets: from_assets_mints,
+        to_assets: to_assets_mints,
+        amounts,
+        min_out,
+        dex_tags,
+        deadline,
+        votes_yes: 0,
+        votes_no: 0,
+        executed: false,
+        legs_executed: 0,
+        snapshot_supply,
+        created_at: current_time,
+        bond: fund_data.config.proposal_bond,
+        cancelled: false,
+        is_initialized: true,
+        roles: 0,
+    };
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+    register_proposal(program_id, proposer_account_info, registry_account_info, system_program_info, &fund_pda, proposal_account_info.key)?;
+    user_data.num_proposals = user_data.num_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.open_proposals = user_data.open_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
+
+    fund_data.active_proposals = fund_data.active_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    fund_data.last_proposal_at = current_time;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    events::emit_proposal_created(fund_account_info.key, proposal_account_info.key, proposer_account_info.key, proposal_kind, deadline);
+
+    Ok(())
+}
+
+// Creates proposals without asset legs: membership changes, withdrawals and config changes.
+// `target` is the member to add/remove, or the withdrawal recipient.
+#[allow(clippy::too_many_arguments)]
+fn process_init_governance_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_kind: u8,
+    target: Pubkey,
+    lamports: u64,
+    config_change: ConfigChange,
+    roles: u8,
+    deadline: i64,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // Proposer's Fund-specific Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let registry_account_info = next_account_info(accounts_iter)?; // Fund's Proposal Registry PDA
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    // Proposer needs to be signer
+    if !proposer_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Investment proposals have their own instruction
+    if proposal_kind == PROPOSAL_KIND_INVESTMENT {
+        return Err(FundError::InvalidInstruction.into());
+    }
+
+    // Derive PDAs and check for equality with the provided ones
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_specific_pda(program_id, &fund_pda, proposer_account_info.key);
+    assert_owned_by(user_specific_pda_info, program_id)?;
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Proposer must be a member, and their Fund-specific account must belong to this Fund
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+    if !fund_data.members.contains(proposer_account_info.key) || user_data.fund != fund_pda {
+        return Err(FundError::NotAFundMember.into());
+    }
+    if user_data.roles & required_role(proposal_kind) == 0 {
+        return Err(FundError::InsufficientPermissions.into());
+    }
+    if fund_data.config.max_open_proposals_per_member > 0 &&
+       user_data.open_proposals >= fund_data.config.max_open_proposals_per_member {
+        return Err(FundError::TooManyOpenProposals.into());
+    }
+
+    // Proposal must leave the Fund's voting window
+    validate_deadline(deadline, current_time, fund_data.config.min_voting_period)?;
+
+    // Proposer must have at least the Fund's minimum deposit at stake
+    if user_data.deposit < fund_data.config.min_proposal_deposit {
+        return Err(FundError::InsufficientStake.into());
+    }
+
+    // Snapshot the governance supply, voting power is measured against it
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let snapshot_supply = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?.supply;
+
+    // Candidate must not already be in the Fund, member to be removed must be in it
+    if proposal_kind == PROPOSAL_KIND_ADD_MEMBER {
+        if fund_data.members.contains(&target) {
+            return Err(FundError::AlreadyMember.into());
+        }
+        if fund_data.members.len() >= MAX_FUND_MEMBERS {
+            return Err(FundError::FundFull.into());
+        }
+    }
+    if proposal_kind == PROPOSAL_KIND_SET_ROLES && (!fund_data.members.contains(&target) || roles & !ROLE_ALL != 0) {
+        return Err(FundError::InvalidInstruction.into());
+    }
+    if proposal_kind == PROPOSAL_KIND_REMOVE_MEMBER {
+        if !fund_data.members.contains(&target) {
+            return Err(FundError::MemberNotFound.into());
+        }
+        if target == fund_data.creator {
+            return Err(FundError::CannotRemoveCreator.into());
+        }
+    }
+    // New settings are held to the same rules as at Fund creation
+    if proposal_kind == PROPOSAL_KIND_CONFIG_CHANGE {
+        validate_fund_config(&apply_config_change(&fund_data.config, &config_change))?;
+    }
+    let (target_member, recipient) = if proposal_kind == PROPOSAL_KIND_WITHDRAWAL {
+        (Pubkey::default(), target)
+    } else {
+        (target, Pubkey::default())
+    };
+
+    // Create Proposal Account with no asset legs
+    let proposal_space = InvestmentProposalAccount::space(0);
+    create_proposal_pda(
+        program_id,
+        proposer_account_info,
+        fund_account_info,
+        proposal_account_info,
+        system_program_info,
+        user_data.num_proposals,
+        proposal_space,
+        fund_data.config.proposal_bond,
+    )?;
+
+    // Deserialization and Serialization of Proposal Account data
+    let proposal_data = InvestmentProposalAccount {
+        version: PROPOSAL_ACCOUNT_VERSION,
+        fund: *fund_account_info.key,
+        proposer: *proposer_account_info.key,
+        proposal_kind,
+        target_member,
+        recipient,
+        lamports,
+        config_change,
+        from_assets: vec![],
+        to_assets: vec![],
+        amounts: vec![],
+        min_out: vec![],
+        dex_tags: vec![],
+        deadline,
+        votes_yes: 0,
+        votes_no: 0,
+        executed: false,
+        legs_executed: 0,
+        snapshot_supply,
+        created_at: current_time,
+        bond: fund_data.config.proposal_bond,
+        cancelled: false,
+        is_initialized: true,
+        roles,
+    };
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+    register_proposal(program_id, proposer_account_info, registry_account_info, system_program_info, &fund_pda, proposal_account_info.key)?;
+    user_data.num_proposals = user_data.num_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.open_proposals = user_data.open_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
+
+    fund_data.active_proposals = fund_data.active_proposals.checked_add(1).ok_or(FundError::ArithmeticOverflow)?;
+    fund_data.last_proposal_at = current_time;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    events::emit_proposal_created(fund_account_info.key, proposal_account_info.key, proposer_account_info.key, proposal_kind, deadline);
+
+    Ok(())
+}
+
+fn process_vote_on_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    vote: u8,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let voter_account_info = next_account_info(accounts_iter)?;
+    let vote_account_info = next_account_info(accounts_iter)?;
+    let proposal_account_info = next_account_info(accounts_iter)?;
+    let system_program_info = next_account_info(accounts_iter)?;
+    let user_specific_pda_info = next_account_info(accounts_iter)?;
+    let fund_account_info = next_account_info(accounts_iter)?;
+    let governance_token_mint_info = next_account_info(accounts_iter)?;
+    let voter_token_account_info = next_account_info(accounts_iter)?;
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    if !voter_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_specific_pda(program_id, &fund_pda, voter_account_info.key);
+    let (vote_pda, _vote_bump) = Pubkey::find_program_address(&[b"vote", proposal_account_info.key.as_ref(), voter_account_info.key.as_ref()], program_id);
+    let token_account = spl_associated_token_account::get_associated_token_address(
+        voter_account_info.key,
+        governance_token_mint_info.key
+    );
+
+    if *fund_account_info.key != fund_pda ||
+        *user_specific_pda_info.key != user_pda ||
+        *vote_account_info.key != vote_pda ||
+        token_account != *voter_token_account_info.key {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Proposal gets its tally mutated, so it must be writable and owned by this program
+    if !proposal_account_info.is_writable || proposal_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+    let (governance_mint, _governance_bump) = derive_governance_mint_pda(program_id, &fund_pda);
+    if *governance_token_mint_info.key != governance_mint {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+
+    // Proposal must belong to this Fund
+    let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    if proposal_data.deadline < current_time {
+        return Err(FundError::VotingCeased.into());
+    }
+    if proposal_data.cancelled {
+        return Err(FundError::ProposalCancelled.into());
+    }
+
+    // Vote PDA is unique per (proposal, voter), so an existing account means the member already voted
+    if !vote_account_info.data_is_empty() {
+        return Err(FundError::AlreadyVoted.into());
+    }
+    create_vote_pda(program_id, voter_account_info, vote_account_info, system_program_info, proposal_account_info.key, voter_account_info.key)?;
+
+    if user_specific_pda_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if !user_data.is_active {
+        return Err(FundError::NotAFundMember.into());
+    }
+    let voting_power = tally_vote(&mut proposal_data, &user_data, &fund_data.config, current_time, vote)?;
+    VoteAccount {
+        version: VOTE_ACCOUNT_VERSION,
+        voter: *voter_account_info.key,
+        vote,
+        voting_power,
+        is_initialized: true,
+    }.serialize(&mut &mut vote_account_info.data.borrow_mut()[..])?;
+    events::emit_vote(&fund_pda, proposal_account_info.key, voter_account_info.key, vote, voting_power);
+
+    // Remaining accounts are pairs of a delegator's User-specific PDA and Vote PDA. The delegate
+    // casts the delegator's power too, recorded in the delegator's own Vote PDA so it is only
+    // counted once. Delegators who already voted themselves are skipped.
+    while let (Ok(delegator_user_specific_info), Ok(delegator_vote_info)) = (next_account_info(accounts_iter), next_account_info(accounts_iter)) {
+        if delegator_user_specific_info.owner != program_id {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        let delegator_data = UserSpecificAccount::try_from_slice(&delegator_user_specific_info.data.borrow())?;
+        if !delegator_data.is_initialized {
+            return Err(FundError::AccountNotInitialized.into());
+        }
+        let (delegator_pda, _delegator_bump) = derive_user_specific_pda(program_id, &fund_pda, &delegator_data.pubkey);
+        if *delegator_user_specific_info.key != delegator_pda || delegator_data.delegate != *voter_account_info.key {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        if !delegator_data.is_active || !delegator_vote_info.data_is_empty() {
+            continue;
+        }
+
+        create_vote_pda(program_id, voter_account_info, delegator_vote_info, system_program_info, proposal_account_info.key, &delegator_data.pubkey)?;
+        let delegated_power = tally_vote(&mut proposal_data, &delegator_data, &fund_data.config, current_time, vote)?;
+        VoteAccount {
+            version: VOTE_ACCOUNT_VERSION,
+            voter: delegator_data.pubkey,
+            vote,
+            voting_power: delegated_power,
+            is_initialized: true,
+        }.serialize(&mut &mut delegator_vote_info.data.borrow_mut()[..])?;
+        events::emit_vote(&fund_pda, proposal_account_info.key, &delegator_data.pubkey, vote, delegated_power);
+    }
+
+    extend_deadline_if_sniped(&mut proposal_data, &fund_data.config, current_time);
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn process_batch_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    votes: Vec<(Pubkey, u8)>,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let voter_account_info = next_account_info(accounts_iter)?; // Voter Wallet
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // Voter's User-specific PDA
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+
+    if !voter_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_specific_pda(program_id, &fund_pda, voter_account_info.key);
+    if *fund_account_info.key != fund_pda || *user_specific_pda_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+
+    assert_owned_by(user_specific_pda_info, program_id)?;
+    let user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if !user_data.is_active {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    // Same rules as a single Vote for every proposal in the batch, except that proposals the
+    // member already voted on are skipped instead of failing the whole batch
+    for (proposal, vote) in votes {
+        let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+        let vote_account_info = next_account_info(accounts_iter)?; // Voter's Vote PDA on the proposal
+
+        if *proposal_account_info.key != proposal || !proposal_account_info.is_writable {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        assert_owned_by(proposal_account_info, program_id)?;
+        let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+        if !proposal_data.is_initialized {
+            return Err(FundError::AccountNotInitialized.into());
+        }
+        if proposal_data.fund != fund_pda {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        if proposal_data.deadline < current_time {
+            return Err(FundError::VotingCeased.into());
+        }
+        if proposal_data.cancelled {
+            return Err(FundError::ProposalCancelled.into());
+        }
+
+        if !vote_account_info.data_is_empty() {
+            continue;
+        }
+        create_vote_pda(program_id, voter_account_info, vote_account_info, system_program_info, &proposal, voter_account_info.key)?;
+
+        let voting_power = tally_vote(&mut proposal_data, &user_data, &fund_data.config, current_time, vote)?;
+        VoteAccount {
+            version: VOTE_ACCOUNT_VERSION,
+            voter: *voter_account_info.key,
+            vote,
+            voting_power,
+            is_initialized: true,
+        }.serialize(&mut &mut vote_account_info.data.borrow_mut()[..])?;
+        extend_deadline_if_sniped(&mut proposal_data, &fund_data.config, current_time);
+        proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+        events::emit_vote(&fund_pda, &proposal, voter_account_info.key, vote, voting_power);
+    }
+
+    Ok(())
+}
+
+fn process_change_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    vote: u8,
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let voter_account_info = next_account_info(accounts_iter)?;
+    let vote_account_info = next_account_info(accounts_iter)?;
+    let proposal_account_info = next_account_info(accounts_iter)?;
+    let fund_account_info = next_account_info(accounts_iter)?;
+    let user_specific_pda_info = next_account_info(accounts_iter)?;
+
+    if !voter_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_pda, _user_bump) = derive_user_specific_pda(program_id, &fund_pda, voter_account_info.key);
+    let (vote_pda, _vote_bump) = Pubkey::find_program_address(&[b"vote", proposal_account_info.key.as_ref(), voter_account_info.key.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda ||
+        *user_specific_pda_info.key != user_pda ||
+        *vote_account_info.key != vote_pda ||
+        vote_account_info.owner != program_id ||
+        proposal_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Members who left or were removed since voting can't move their vote any more
+    assert_owned_by(user_specific_pda_info, program_id)?;
+    let user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if !user_data.is_active {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.is_paused {
+        return Err(FundError::FundPaused.into());
+    }
+
+    let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    if proposal_data.deadline < current_time {
+        return Err(FundError::VotingCeased.into());
+    }
+    if proposal_data.cancelled {
+        return Err(FundError::ProposalCancelled.into());
+    }
+
+    // Move the recorded voting power from the old side of the tally to the new one
+    let mut vote_data = VoteAccount::try_from_slice(&vote_account_info.data.borrow())?;
+    if !vote_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if vote_data.vote == 1 {
+        proposal_data.votes_yes = proposal_data.votes_yes.saturating_sub(vote_data.voting_power);
+    } else {
+        proposal_data.votes_no = proposal_data.votes_no.saturating_sub(vote_data.voting_power);
+    }
+
+    if vote == 1 {
+        proposal_data.votes_yes = proposal_data.votes_yes.checked_add(vote_data.voting_power).ok_or(FundError::ArithmeticOverflow)?;
+    } else {
+        proposal_data.votes_no = proposal_data.votes_no.checked_add(vote_data.voting_power).ok_or(FundError::ArithmeticOverflow)?;
+    }
+
+    extend_deadline_if_sniped(&mut proposal_data, &fund_data.config, current_time);
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+
+    vote_data.vote = vote;
+    vote_data.serialize(&mut &mut vote_account_info.data.borrow_mut()[..])?;
+
+    events::emit_vote(&fund_pda, proposal_account_info.key, voter_account_info.key, vote, vote_data.voting_power);
+
+    Ok(())
+}
+
+fn process_delegate_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delegate: Pubkey,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Member Wallet
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // Member's User-specific PDA
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_account_info.key);
+    if *fund_account_info.key != fund_pda ||
+       *user_specific_pda_info.key != user_specific_pda ||
+       user_specific_pda_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if !user_data.is_active {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    // Delegate must be another active member who isn't delegating themselves, so chains and
+    // cycles can't form
+    if delegate != Pubkey::default() {
+        let delegate_user_specific_info = next_account_info(accounts_iter)?; // Delegate's User-specific PDA
+        let (delegate_pda, _delegate_bump) = derive_user_specific_pda(program_id, &fund_pda, &delegate);
+        if *delegate_user_specific_info.key != delegate_pda || delegate_user_specific_info.owner != program_id {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        let delegate_data = UserSpecificAccount::try_from_slice(&delegate_user_specific_info.data.borrow())?;
+        if !delegate_data.is_initialized {
+            return Err(FundError::AccountNotInitialized.into());
+        }
+        if delegate == *member_account_info.key || !delegate_data.is_active || delegate_data.delegate != Pubkey::default() {
+            return Err(FundError::InvalidDelegate.into());
+        }
+    }
+
+    user_data.delegate = delegate;
+    user_data.serialize(&mut &mut user_specific_pda_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn process_close_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let closer_account_info = next_account_info(accounts_iter)?; // Proposer or Fund Creator Wallet
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet, receives the rent
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault Account
+    let registry_account_info = next_account_info(accounts_iter)?; // Fund's Proposal Registry PDA
+    let proposer_specific_info = next_account_info(accounts_iter)?; // Proposer's User-specific PDA
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault's WSOL Token Account, receives forfeited bonds
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+
+    // Programs invoked below must be the canonical ones
+    assert_program(token_program_info, &spl_token::id())?;
+
+    // Closer should be signer
+    if !closer_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda || proposal_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != fund_pda || proposal_data.proposer != *proposer_account_info.key {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Only the proposer or the Fund's creator can close a proposal
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if *closer_account_info.key != proposal_data.proposer && *closer_account_info.key != fund_data.creator {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Proposal must be settled, or have failed its vote, or have passed but gone unexecuted past
+    // its execution window
+    let settled = proposal_data.executed || proposal_data.cancelled;
+    let failed = current_time > proposal_data.deadline &&
+        check_proposal_passes(&proposal_data, &fund_data.config).is_err();
+    let lapsed = current_time > proposal_data.deadline
+        .saturating_add(fund_data.config.execution_delay)
+        .saturating_add(EXECUTION_GRACE_PERIOD);
+    if !settled && !failed && !lapsed {
+        return Err(FundError::ProposalStillActive.into());
+    }
+
+    // Executed and cancelled proposals were already taken off the active count
+    if !settled {
+        fund_data.active_proposals = fund_data.active_proposals.saturating_sub(1);
+        fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+    }
+
+    // Spam proposals, those that closed with less than half of the yes votes quorum asks for,
+    // forfeit their bond to the vault's WSOL, where it backs the members' governance tokens. Every
+    // other proposal gets its bond back with the rent, including cancelled ones, which never took a
+    // vote.
+    let (vault_pda, _vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+    if *vault_account_info.key != vault_pda || *vault_wsol_info.key != expected_vault_wsol {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let is_spam = !settled &&
+        (proposal_data.votes_yes as u128) * 10000 * 2 < (quorum_base(&proposal_data, &fund_data.config) as u128) * (fund_data.config.quorum_bps as u128);
+    if is_spam && proposal_data.bond > 0 {
+        **proposal_account_info.try_borrow_mut_lamports()? -= proposal_data.bond;
+        **vault_wsol_info.try_borrow_mut_lamports()? += proposal_data.bond;
+
+        // Fold the bond into the WSOL account's token balance
+        invoke(
+            &spl_token::instruction::sync_native(
+                token_program_info.key,
+                vault_wsol_info.key,
+            )?,
+            &[
+                vault_wsol_info.clone(),
+                token_program_info.clone(),
+            ]
+        )?;
+
+        assert_owned_by(vault_account_info, program_id)?;
+        let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+        if fresh_vault_value(&vault_data, current_time).is_some() {
+            vault_data.cached_value = vault_data.cached_value.saturating_add(proposal_data.bond);
+            vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+        }
+    }
+
+    unregister_proposal(program_id, registry_account_info, &fund_pda, proposal_account_info.key)?;
+
+    // Frees one of the proposer's open proposal slots, unless they have since left the Fund
+    let (proposer_specific_pda, _proposer_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, &proposal_data.proposer);
+    if *proposer_specific_info.key != proposer_specific_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    if !proposer_specific_info.data_is_empty() {
+        assert_owned_by(proposer_specific_info, program_id)?;
+        let mut proposer_data = UserSpecificAccount::try_from_slice(&proposer_specific_info.data.borrow())?;
+        if proposer_data.is_initialized {
+            proposer_data.open_proposals = proposer_data.open_proposals.saturating_sub(1);
+            proposer_data.serialize(&mut &mut proposer_specific_info.data.borrow_mut()[..])?;
+        }
+    }
+
+    // Return the rent to the proposer and wipe the account
+    let lamports = proposal_account_info.lamports();
+    **proposal_account_info.try_borrow_mut_lamports()? = 0;
+    **proposer_account_info.try_borrow_mut_lamports()? += lamports;
+    proposal_account_info.data.borrow_mut().fill(0);
+
+    msg!("Proposal closed and rent returned to proposer");
+
+    Ok(())
+}
+
+fn process_cancel_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    // Proposer should be signer
+    if !proposer_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(proposal_account_info, program_id)?;
+    assert_owned_by(fund_account_info, program_id)?;
+
+    let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Only the original proposer can withdraw their proposal
+    if proposal_data.proposer != *proposer_account_info.key {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+    if proposal_data.executed {
+        return Err(FundError::ProposalAlreadyExecuted.into());
+    }
+    if proposal_data.cancelled {
+        return Err(FundError::ProposalCancelled.into());
+    }
+    if proposal_data.deadline < current_time {
+        return Err(FundError::VotingCeased.into());
+    }
+
+    // Once members have voted the proposal is theirs too, the proposer can't pull it
+    if proposal_data.votes_yes > 0 || proposal_data.votes_no > 0 {
+        return Err(FundError::ProposalHasVotes.into());
+    }
+
+    proposal_data.cancelled = true;
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+
+    // Cancelled proposals no longer count as outstanding, CloseProposal reclaims the rent
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    fund_data.active_proposals = fund_data.active_proposals.saturating_sub(1);
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn process_close_vault_token_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Member Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA, receives the rent
+    let vault_token_account_info = next_account_info(accounts_iter)?; // Vault's Token Account to close
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+
+    // Programs invoked below must be the canonical ones
+    assert_program(token_program_info, &spl_token::id())?;
+
+    if !member_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Any member may sweep the Vault's empty accounts
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if !fund_data.members.contains(member_account_info.key) {
+        return Err(FundError::NotAFundMember.into());
+    }
+
+    // Only the Vault's own token accounts, and only once they hold nothing
+    assert_owned_by(vault_token_account_info, &spl_token::id())?;
+    let vault_token_data = TokenAccount::unpack(&vault_token_account_info.data.borrow())?;
+    if vault_token_data.owner != vault_pda {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    if vault_token_data.amount != 0 {
+        return Err(FundError::TokenAccountNotEmpty.into());
+    }
+
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            token_program_info.key,
+            vault_token_account_info.key,
+            vault_account_info.key,
+            vault_account_info.key,
+            &[],
+        )?,
+        &[
+            vault_token_account_info.clone(),
+            vault_account_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&[b"vault", fund_pda.as_ref(), &[vault_bump]]],
+    )?;
+
+    Ok(())
+}
+
+fn process_close_fund(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let creator_account_info = next_account_info(accounts_iter)?; // Creator Wallet, receives the rent
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault Account
+    let vault_wsol_info = next_account_info(accounts_iter)?; // Vault's WSOL ATA
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let reserve_info = next_account_info(accounts_iter)?; // Fund's Reserve WSOL Account
+
+    // Programs invoked below must be the canonical ones
+    assert_program(token_program_info, &spl_token::id())?;
+
+    if !creator_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+    if *fund_account_info.key != fund_pda || *vault_account_info.key != vault_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+    if *vault_wsol_info.key != expected_vault_wsol {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    // Only the creator can close the Fund, and only once every proposal is resolved
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if *creator_account_info.key != fund_data.creator {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+    if fund_data.active_proposals != 0 {
+        return Err(FundError::OutstandingProposals.into());
+    }
+    if *governance_mint_info.key != fund_data.governance_mint {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let governance_supply = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?.supply;
+
+    // Only WSOL is split between members, the vault's other holdings must be sold off first
+    for accepted in fund_data.accepted_mints.iter().filter(|accepted| accepted.mint != spl_token::native_mint::id()) {
+        let vault_token_info = next_account_info(accounts_iter)?; // Vault's ATA for the accepted mint
+        if *vault_token_info.key != spl_associated_token_account::get_associated_token_address(&vault_pda, &accepted.mint) {
+            return Err(FundError::InvalidTokenAccount.into());
+        }
+        if vault_token_info.data_is_empty() {
+            continue;
+        }
+        assert_owned_by(vault_token_info, &spl_token::id())?;
+        if TokenAccount::unpack(&vault_token_info.data.borrow())?.amount != 0 {
+            return Err(FundError::TokenAccountNotEmpty.into());
+        }
+    }
+
+    // Every member's User-specific PDA and WSOL account must be passed, in the Fund's member order
+    let mut member_accounts = Vec::with_capacity(fund_data.members.len());
+    let mut total_balance: u128 = 0;
+    for member in fund_data.members.iter() {
+        let user_specific_info = next_account_info(accounts_iter)?;
+        let member_wsol_info = next_account_info(accounts_iter)?;
+
+        let (user_specific_pda, _bump) = derive_user_specific_pda(program_id, &fund_pda, member);
+        if *user_specific_info.key != user_specific_pda || user_specific_info.owner != program_id {
+            return Err(FundError::InvalidAccountData.into());
+        }
+        assert_owned_by(member_wsol_info, &spl_token::id())?;
+        let member_wsol_data = TokenAccount::unpack(&member_wsol_info.data.borrow())?;
+        if member_wsol_data.mint != spl_token::native_mint::id() || member_wsol_data.owner != *member {
+            return Err(FundError::InvalidTokenAccount.into());
+        }
+
+        let user_data = UserSpecificAccount::try_from_slice(&user_specific_info.data.borrow())?;
+        if !user_data.is_initialized {
+            return Err(FundError::AccountNotInitialized.into());
+        }
+        total_balance += user_data.governance_token_balance as u128;
+        member_accounts.push((user_specific_info, member_wsol_info, user_data));
+    }
+
+    // Deposits mint and withdrawals burn exactly what they record, so members' balances fall short
+    // of the supply only while removed members still hold a stake. It's theirs to withdraw first.
+    if total_balance != governance_supply as u128 {
+        return Err(FundError::FormerMemberBalances.into());
+    }
+
+    // Native SOL the vault holds above its rent belongs to the members too, wrap it so it's split
+    // with the WSOL and only the rent goes to the creator
+    assert_owned_by(vault_wsol_info, &spl_token::id())?;
+    let spare_lamports = vault_spare_lamports(vault_account_info)?;
+    if spare_lamports > 0 {
+        wrap_sol(vault_account_info, vault_wsol_info, token_program_info, spare_lamports)?;
+    }
+
+    // Split the vault's and the reserve's WSOL by governance token balance, rounding remainder goes
+    // to the first member
+    let mut reserve_balance = reserve_wsol_amount(program_id, &fund_pda, reserve_info)?;
+    let vault_amount = TokenAccount::unpack(&vault_wsol_info.data.borrow())?.amount
+        .checked_add(reserve_balance)
+        .ok_or(FundError::ArithmeticOverflow)?;
+    let mut shares: Vec<u64> = member_accounts.iter()
+        .map(|(_, _, user_data)| {
+            ((vault_amount as u128) * (user_data.governance_token_balance as u128))
+                .checked_div(total_balance)
+                .unwrap_or(0) as u64
+        })
+        .collect();
+    let distributed: u64 = shares.iter().sum();
+    if let Some(first_share) = shares.first_mut() {
+        *first_share += vault_amount - distributed;
+    }
+
+    let vault_seeds: &[&[u8]] = &[b"vault", fund_account_info.key.as_ref(), &[vault_bump]];
+    for ((user_specific_info, member_wsol_info, mut user_data), share) in member_accounts.into_iter().zip(shares) {
+        pay_out_wsol(
+            vault_account_info,
+            reserve_info,
+            reserve_balance,
+            vault_wsol_info,
+            member_wsol_info,
+            token_program_info,
+            vault_seeds,
+            share,
+        )?;
+        reserve_balance -= share.min(reserve_balance);
+
+        // Governance tokens can't be burnt without each holder's signature, so the member's
+        // recorded stake is cleared instead; with the Fund closed the tokens carry no claim.
+        user_data.deposit = 0;
+        user_data.governance_token_balance = 0;
+        user_data.is_active = false;
+        user_data.serialize(&mut &mut user_specific_info.data.borrow_mut()[..])?;
+    }
+
+    // Close the vault's WSOL account and the reserve, if it was ever created, their rent goes to the
+    // creator
+    for wsol_info in [vault_wsol_info, reserve_info] {
+        if wsol_info.data_is_empty() {
+            continue;
+        }
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                token_program_info.key,
+                wsol_info.key,
+                creator_account_info.key,
+                vault_account_info.key,
+                &[],
+            )?,
+            &[
+                wsol_info.clone(),
+                creator_account_info.clone(),
+                vault_account_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[vault_seeds],
+        )?;
+    }
+
+    // Return the rent of the vault and Fund accounts to the creator and wipe them
+    for account_info in [vault_account_info, fund_account_info] {
+        let lamports = account_info.lamports();
+        **account_info.try_borrow_mut_lamports()? = 0;
+        **creator_account_info.try_borrow_mut_lamports()? += lamports;
+        account_info.data.borrow_mut().fill(0);
+    }
+
+    msg!("Fund closed and vault distributed to members");
+
+    Ok(())
+}
+
+fn process_get_fund_summary(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda || fund_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    assert_owned_by(governance_mint_info, &spl_token::id())?;
+    let governance_mint_data = spl_token::state::Mint::unpack(&governance_mint_info.data.borrow())?;
+
+    // Read-only: nothing is written, the summary is handed back as return data
+    let summary = FundSummary {
+        member_count: fund_data.members.len() as u32,
+        total_deposit: fund_data.total_deposit,
+        governance_supply: governance_mint_data.supply,
+        active_proposal_count: fund_data.active_proposals,
+        is_paused: fund_data.is_paused,
+        deposits_frozen: fund_data.deposits_frozen,
+        admin_nonce: fund_data.admin_nonce,
+    };
+    set_return_data(&summary.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_get_members(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    offset: u32,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+
+    // Read-only: one page of members starting at `offset`, empty once past the end
+    let page = MembersPage {
+        total_members: fund_data.members.len() as u32,
+        offset,
+        members: fund_data.members
+            .iter()
+            .skip(offset as usize)
+            .take(MEMBERS_PAGE_SIZE)
+            .copied()
+            .collect(),
+    };
+    set_return_data(&page.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_get_open_proposals(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    offset: u32,
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let registry_account_info = next_account_info(accounts_iter)?; // Fund's Proposal Registry PDA
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (registry_pda, _registry_bump) = derive_proposal_registry_pda(program_id, &fund_pda);
+    if *fund_account_info.key != fund_pda || *registry_account_info.key != registry_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Registry only exists once the Fund's first proposal is created
+    let proposals = if registry_account_info.data_is_empty() {
+        vec![]
+    } else {
+        assert_owned_by(registry_account_info, program_id)?;
+        ProposalRegistry::try_from_slice(&registry_account_info.data.borrow())?.proposals
+    };
+
+    // Read-only: one page of proposals starting at `offset`, empty once past the end
+    let page = ProposalsPage {
+        total_proposals: proposals.len() as u32,
+        offset,
+        proposals: proposals
+            .into_iter()
+            .skip(offset as usize)
+            .take(PROPOSALS_PAGE_SIZE)
+            .collect(),
+    };
+    set_return_data(&page.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_get_proposal_status(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    if *fund_account_info.key != fund_pda ||
+       fund_account_info.owner != program_id ||
+       proposal_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != fund_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+
+    // Same tally rules as execution, so the preview matches what Execute would do
+    let passes = check_proposal_passes(&proposal_data, &fund_data.config).is_ok();
+    let status = if proposal_data.executed {
+        ProposalStatus::Executed
+    } else if proposal_data.cancelled {
+        ProposalStatus::Cancelled
+    } else if current_time > proposal_data.deadline && !passes {
+        ProposalStatus::Expired
+    } else if proposal_data.votes_yes == 0 && proposal_data.votes_no == 0 {
+        ProposalStatus::Pending
+    } else if passes {
+        ProposalStatus::Passing
+    } else {
+        ProposalStatus::Failing
+    };
+    set_return_data(&status.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_get_member_position(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fund_name: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let member_account_info = next_account_info(accounts_iter)?; // Member Wallet
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let user_specific_pda_info = next_account_info(accounts_iter)?; // Member's User-specific PDA
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let governance_token_account_info = next_account_info(accounts_iter)?; // Member's Governance Token Account
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_account_info.key);
+    if *fund_account_info.key != fund_pda ||
+       *user_specific_pda_info.key != user_specific_pda ||
+       user_specific_pda_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    let expected_ata = spl_associated_token_account::get_associated_token_address(member_account_info.key, governance_mint_info.key);
+    if *governance_token_account_info.key != expected_ata {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    // Member may not have deposited yet, in which case there is no governance token account
+    let governance_ata_balance = if governance_token_account_info.data_is_empty() {
+        0
+    } else {
+        assert_owned_by(governance_token_account_info, &spl_token::id())?;
+        TokenAccount::unpack(&governance_token_account_info.data.borrow())?.amount
+    };
+
+    // Read-only: nothing is written, the position is handed back as return data
+    let user_data = UserSpecificAccount::try_from_slice(&user_specific_pda_info.data.borrow())?;
+    if !user_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    let position = MemberPosition {
+        deposit: user_data.deposit,
+        governance_token_balance: user_data.governance_token_balance,
+        governance_ata_balance,
+        num_proposals: user_data.num_proposals,
+        is_active: user_data.is_active,
+        join_time: user_data.join_time,
+        last_deposit_time: user_data.last_deposit_time,
+    };
+    set_return_data(&position.try_to_vec()?);
+
+    Ok(())
+}
+
+//    LEAVING FUND
+//    delete fund specific pda
+//    change member array size in fund pda
+//    change User account pda's size
+
+fn process_leave_fund(
+    program_id: &Pubkey,
+    fund_name: String,
+    accounts: &[AccountInfo],
+    
+) -> ProgramResult {
+
+    // let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let member_wallet_info =next_account_info(accounts_iter)?;
+    let user_specific_info = next_account_info(accounts_iter)?; // to be deleted
+    let fund_account_info= next_account_info(accounts_iter)?;  // to change number of members
+    let user_account_info=next_account_info(accounts_iter)?;   // to change fund details
+    // let system_program_info = next_account_info(accounts_iter)?;  // to delete fund specific account
+    // let voter_account_info = next_account_info(accounts_iter)?;
+    // let proposal_account_info = next_account_info(accounts_iter)?;
+
+    if !member_wallet_info.is_signer {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+
+    let (fund_pda, _fund_bump) = derive_fund_pda(program_id, fund_name.as_bytes());
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, &fund_pda, member_wallet_info.key);
+    let (user_pda, _user_bump) = derive_user_pda(program_id, member_wallet_info.key);
+    assert_owned_by(user_account_info, program_id)?;
+    let mut user_data = UserAccount::try_from_slice(&user_account_info.data.borrow())?;
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    // let (proposal_pda, _proposal_bump) = Pubkey::find_program_address(&[b"proposal-investment", user_account_info.key.as_ref(), &[user_data.num_proposals]], program_id);
+    // let proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if *fund_account_info.key != fund_pda ||
+    *user_specific_info.key != user_specific_pda ||
+    *user_account_info.key != user_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+
+
+    if !user_specific_info.data_is_empty(){
+        // Closing the Fund-specific account would lose the member's recorded stake, it has to be
+        // withdrawn first
+        assert_owned_by(user_specific_info, program_id)?;
+        let user_specific_data = UserSpecificAccount::try_from_slice(&user_specific_info.data.borrow())?;
+        if user_specific_data.governance_token_balance != 0 {
+            return Err(FundError::StakeNotWithdrawn.into());
+        }
+
+        let lamports = **user_specific_info.try_borrow_lamports()?;
+        **member_wallet_info.try_borrow_mut_lamports()? += lamports;
+        **user_specific_info.try_borrow_mut_lamports()? = 0;
+    
+        // let mut data = user_specific_info.try_borrow_mut_data()?;
+        // for byte in data.iter_mut() {
+        //     *byte = 0;
+        // }
+    
+        msg!("User-specific fund account closed and lamports sent to user");
+        
+    }
+    
+    let current_rent = user_account_info.lamports();
+
+    let mut flag = false;
+
+    user_data.funds.retain(|key| {
+        let keep = key != fund_account_info.key;
+        if !keep {
+            flag = true;
+        }
+        keep
+    });
+
+    let rent = Rent::get()?;
+
+    // Remove member from the Fund and shrink it, returning the freed rent to the member. Members who
+    // joined through their first deposit may never have listed the Fund in their User PDA.
+    if let Some(position) = fund_data.members.iter().position(|key| key == member_wallet_info.key) {
+        fund_data.members.remove(position);
+
+        let fund_current_rent = fund_account_info.lamports();
+        let fund_new_size = fund_account_info.data_len() - 32;
+        let fund_new_rent = rent.minimum_balance(fund_new_size);
+        if fund_new_rent < fund_current_rent {
+            **fund_account_info.try_borrow_mut_lamports()? -= fund_current_rent - fund_new_rent;
+            **member_wallet_info.try_borrow_mut_lamports()? += fund_current_rent - fund_new_rent;
+        }
+
+        fund_account_info.realloc(fund_new_size, false)?;
+    }
+
+    if flag {
+        let current_size = user_account_info.data_len();
+        let new_size= current_size-32;
+        let new_rent = rent.minimum_balance(new_size);
+    
+        if new_rent < current_rent {
+            // let lamports = **user_account_info.try_borrow_lamports()?;
+            **user_account_info.try_borrow_mut_lamports()? -= current_rent-new_rent;
+            **member_wallet_info.try_borrow_mut_lamports()? += current_rent-new_rent;
+        }
+
+        user_account_info.realloc(new_size, false)?;
+    }
+
+    user_data.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+
+// fn process_delete_fund(
+//     _program_id: &Pubkey,
+//     accounts: &[AccountInfo],
+//     // fund_name: String,
+// ) -> ProgramResult {
+//     let accounts_iter = &mut accounts.iter();
+//     let fund_account_info = next_account_info(accounts_iter)?;
+//     let recipient_account_info = next_account_info(accounts_iter)?;
+//     let vault_account_info = next_account_info(accounts_iter)?;
+//     let governance_mint_info = next_account_info(accounts_iter)?;
+
+//     **recipient_account_info.lamports.borrow_mut() += **governance_mint_info.lamports.borrow();
+//     **governance_mint_info.lamports.borrow_mut() = 0;
+//     let mut gov_data = governance_mint_info.try_borrow_mut_data()?;
+//     for byte in gov_data.iter_mut() {
+//         *byte = 0;
+//     }
+
+//     **recipient_account_info.lamports.borrow_mut() += **vault_account_info.lamports.borrow();
+//     **vault_account_info.lamports.borrow_mut() = 0;
+//     let mut gov_data = vault_account_info.try_borrow_mut_data()?;
+//     for byte in gov_data.iter_mut() {
+//         *byte = 0;
+//     }
+
+//     **recipient_account_info.lamports.borrow_mut() += **fund_account_info.lamports.borrow();
+//     **fund_account_info.lamports.borrow_mut() = 0;
+//     let mut gov_data = fund_account_info.try_borrow_mut_data()?;
+//     for byte in gov_data.iter_mut() {
+//         *byte = 0;
+//     }
+
+//     Ok(())
+// }
+
+fn process_simulate_execute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal: Pubkey,
+    routes: Vec<SwapRoute>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+
+    if *proposal_account_info.key != proposal {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    assert_owned_by(proposal_account_info, program_id)?;
+    let proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if proposal_data.fund != *fund_account_info.key || proposal_data.proposal_kind != PROPOSAL_KIND_INVESTMENT {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    if proposal_data.executed {
+        return Err(FundError::ProposalAlreadyExecuted.into());
+    }
+    if proposal_data.cancelled {
+        return Err(FundError::ProposalCancelled.into());
+    }
+    assert_owned_by(fund_account_info, program_id)?;
+    let fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+
+    // Same pending legs, routes and pool checks as Execute, but each leg is quoted instead of
+    // swapped. Jupiter has no on-chain quote, so its legs report the route's off-chain quote.
+    let pending_legs: Vec<usize> = (0..proposal_data.amounts.len())
+        .filter(|i| proposal_data.legs_executed & (1 << i) == 0)
+        .collect();
+    if routes.is_empty() || routes.len() > pending_legs.len() {
+        return Err(FundError::InvalidInstruction.into());
+    }
+
+    let mut expected_out: Vec<u64> = Vec::with_capacity(routes.len());
+    for (&i, route) in pending_legs.iter().zip(routes.iter()) {
+        let dex_program_info = next_account_info(accounts_iter)?; // DEX Program
+
+        if !is_dex_allowed(fund_data.config.dex_allowlist, proposal_data.dex_tags[i]) {
+            return Err(FundError::UnsupportedDex.into());
+        }
+        let out = match proposal_data.dex_tags[i] {
+            DEX_JUPITER => {
+                if *dex_program_info.key != JUPITER_PROGRAM_ID {
+                    return Err(FundError::InvalidInstruction.into());
+                }
+                route.quoted_out_amount
+            }
+            DEX_ORCA_WHIRLPOOL => {
+                let pool_accounts: Vec<AccountInfo> = accounts_iter
+                    .take(ORCA_POOL_ACCOUNTS)
+                    .cloned()
+                    .collect();
+                if *dex_program_info.key != ORCA_WHIRLPOOL_PROGRAM_ID {
+                    return Err(FundError::InvalidInstruction.into());
+                }
+                let a_to_b = orca_swap_direction(&pool_accounts, &proposal_data.from_assets[i], &proposal_data.to_assets[i])?;
+                orca_quote(&pool_accounts, proposal_data.amounts[i], a_to_b)?
+            }
+            _ => return Err(FundError::UnsupportedDex.into()),
+        };
+        expected_out.push(out);
+    }
+    set_return_data(&expected_out.try_to_vec()?);
+
+    Ok(())
+}
+
+fn process_execute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal: Pubkey,
+    routes: Vec<SwapRoute>,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let accounts_iter = &mut accounts.iter();
+    let executor_account_info = next_account_info(accounts_iter)?; // Executor Wallet
+    let proposal_account_info = next_account_info(accounts_iter)?; // Proposal Account
+    let fund_account_info = next_account_info(accounts_iter)?; // Fund Account
+    let vault_account_info = next_account_info(accounts_iter)?; // Vault PDA
+    let governance_mint_info = next_account_info(accounts_iter)?; // Governance Mint Account
+    let system_program_info = next_account_info(accounts_iter)?; // System Program
+    let token_program_info = next_account_info(accounts_iter)?; // Token Program
+    let proposer_account_info = next_account_info(accounts_iter)?; // Proposer Wallet, receives the bond back
+
+    // Programs invoked below must be the canonical ones
+    assert_program(system_program_info, &system_program::id())?;
+    assert_program(token_program_info, &spl_token::id())?;
+
+    // Executor should be signer
+    if !executor_account_info.is_signer {
+        return Err(FundError::MissingRequiredSignature.into());
+    }
+
+    // Proposal must be the one requested and must be owned by this program
+    if *proposal_account_info.key != proposal || proposal_account_info.owner != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Proposal must belong to the provided Fund, and Vault must be derived from that Fund
+    let mut proposal_data = InvestmentProposalAccount::try_from_slice(&proposal_account_info.data.borrow())?;
+    if !proposal_data.is_initialized {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(&[b"vault", fund_account_info.key.as_ref()], program_id);
+    if proposal_data.fund != *fund_account_info.key || *vault_account_info.key != vault_pda ||
+       proposal_data.proposer != *proposer_account_info.key {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // A proposal can only be settled once
+    if proposal_data.executed {
+        return Err(FundError::ProposalAlreadyExecuted.into());
+    }
+    if proposal_data.cancelled {
+        return Err(FundError::ProposalCancelled.into());
+    }
+
+    // Votes are still accepted at the deadline itself, so execution has to wait until after it
+    if current_time <= proposal_data.deadline {
+        return Err(FundError::VotingStillOpen.into());
+    }
+
+    // Tally must clear majority, quorum and pass threshold
+    assert_owned_by(fund_account_info, program_id)?;
+    let mut fund_data = FundAccount::try_from_slice(&fund_account_info.data.borrow())?;
+    if fund_data.governance_mint != *governance_mint_info.key {
+        return Err(FundError::InvalidGovernanceMint.into());
+    }
+    if current_time < proposal_data.deadline.saturating_add(fund_data.config.execution_delay) {
+        return Err(FundError::ExecutionDelayActive.into());
+    }
+    check_proposal_passes(&proposal_data, &fund_data.config)?;
+
+    match proposal_data.proposal_kind {
+        PROPOSAL_KIND_INVESTMENT => {
+            let ata_program_info = next_account_info(accounts_iter)?; // Associated Token Program
+
+            // Legs can be executed over several transactions. Executor supplies routes for the next
+            // legs still pending, in order, and completed legs are never swapped again.
+            let pending_legs: Vec<usize> = (0..proposal_data.amounts.len())
+                .filter(|i| proposal_data.legs_executed & (1 << i) == 0)
+                .collect();
+            assert_program(ata_program_info, &spl_associated_token_account::id())?;
+            if routes.is_empty() || routes.len() > pending_legs.len() {
+                return Err(FundError::InvalidInstruction.into());
+            }
+
+            for (&i, route) in pending_legs.iter().zip(routes.iter()) {
+                let vault_from_ata_info = next_account_info(accounts_iter)?; // Vault's From Asset ATA
+                let vault_to_ata_info = next_account_info(accounts_iter)?; // Vault's To Asset ATA
+                let to_mint_info = next_account_info(accounts_iter)?; // To Asset Mint
+                let dex_program_info = next_account_info(accounts_iter)?; // DEX Program
+
+                // Legs can't buy the Fund's own governance token, the vault would end up backing its
+                // shares with more of the same shares
+                if proposal_data.to_assets[i] == fund_data.governance_mint {
+                    return Err(FundError::InvalidProposalTarget.into());
+                }
+
+                // Both sides of the swap must be the vault's own token accounts
+                let expected_from_ata = spl_associated_token_account::get_associated_token_address(&vault_pda, &proposal_data.from_assets[i]);
+                let expected_to_ata = spl_associated_token_account::get_associated_token_address(&vault_pda, &proposal_data.to_assets[i]);
+                if *vault_from_ata_info.key != expected_from_ata ||
+                   *vault_to_ata_info.key != expected_to_ata ||
+                   *to_mint_info.key != proposal_data.to_assets[i] {
+                    return Err(FundError::InvalidTokenAccount.into());
+                }
+
+                // Vault must hold enough of the From Asset to cover this leg, the reserve account isn't
+                // one of its ATAs and stays out of reach. SOL legs can make up a WSOL shortfall from
+                // native lamports sitting in the Vault PDA.
+                assert_owned_by(vault_from_ata_info, &spl_token::id())?;
+                let mut vault_from_data = TokenAccount::unpack(&vault_from_ata_info.data.borrow())?;
+                if proposal_data.from_assets[i] == spl_token::native_mint::id() && vault_from_data.amount < proposal_data.amounts[i] {
+                    wrap_sol(vault_account_info, vault_from_ata_info, token_program_info, proposal_data.amounts[i] - vault_from_data.amount)?;
+                    vault_from_data = TokenAccount::unpack(&vault_from_ata_info.data.borrow())?;
+                }
+                if vault_from_data.amount < proposal_data.amounts[i] {
+                    return Err(FundError::NotEnoughFunds.into());
+                }
+
+                // Vault may not hold the To Asset yet, executor pays for its ATA
+                if vault_to_ata_info.data_is_empty() {
+                    invoke(
+                        &create_associated_token_account(
+                            executor_account_info.key,
+                            &vault_pda,
+                            to_mint_info.key,
+                            token_program_info.key,
+                        ),
+                        &[
+                            executor_account_info.clone(),
+                            vault_to_ata_info.clone(),
+                            vault_account_info.clone(),
+                            to_mint_info.clone(),
+                            system_program_info.clone(),
+                            token_program_info.clone(),
+                            ata_program_info.clone(),
+                        ],
+                    )?;
+                }
+
+                // Allowlist may have been narrowed by a config change since the proposal was created
+                if !is_dex_allowed(fund_data.config.dex_allowlist, proposal_data.dex_tags[i]) {
+                    return Err(FundError::UnsupportedDex.into());
+                }
+
+                // Leg's DEX tag selects the venue and the accounts that follow
+                let (swap_ix, mut swap_account_infos) = match proposal_data.dex_tags[i] {
+                    DEX_JUPITER => {
+                        let dex_event_authority_info = next_account_info(accounts_iter)?; // DEX Event Authority
+                        let route_accounts: Vec<AccountInfo> = accounts_iter
+                            .take(route.num_accounts as usize)
+                            .cloned()
+                            .collect();
+                        if route_accounts.len() != route.num_accounts as usize {
+                            return Err(FundError::InvalidAccountData.into());
+                        }
+                        if *dex_program_info.key != JUPITER_PROGRAM_ID || *dex_event_authority_info.key != JUPITER_EVENT_AUTHORITY {
+                            return Err(FundError::InvalidInstruction.into());
+                        }
+
+                        // Vault PDA signs the whole route, so it may touch no vault token account
+                        // besides this leg's two, the reserve and other holdings included
+                        for route_account in route_accounts.iter() {
+                            if route_account.key == vault_from_ata_info.key || route_account.key == vault_to_ata_info.key ||
+                               route_account.owner != &spl_token::id() {
+                                continue;
+                            }
+                            if let Ok(token_data) = TokenAccount::unpack(&route_account.data.borrow()) {
+                                if token_data.owner == vault_pda {
+                                    return Err(FundError::InvalidTokenAccount.into());
+                                }
+                            }
+                        }
+
+                        let swap_ix = build_jupiter_swap_ix(
+                            &vault_pda,
+                            vault_from_ata_info.key,
+                            vault_to_ata_info.key,
+                            to_mint_info.key,
+                            &route_accounts,
+                            proposal_data.amounts[i],
+                            route,
+                        );
+                        let mut swap_account_infos = vec![to_mint_info.clone(), dex_event_authority_info.clone()];
+                        swap_account_infos.extend(route_accounts);
+                        (swap_ix, swap_account_infos)
+                    }
+                    DEX_ORCA_WHIRLPOOL => {
+                        let pool_accounts: Vec<AccountInfo> = accounts_iter
+                            .take(ORCA_POOL_ACCOUNTS)
+                            .cloned()
+                            .collect();
+                        if *dex_program_info.key != ORCA_WHIRLPOOL_PROGRAM_ID {
+                            return Err(FundError::InvalidInstruction.into());
+                        }
+                        let a_to_b = orca_swap_direction(&pool_accounts, &proposal_data.from_assets[i], &proposal_data.to_assets[i])?;
+
+                        let swap_ix = build_orca_swap_ix(
+                            &vault_pda,
+                            vault_from_ata_info.key,
+                            vault_to_ata_info.key,
+                            &pool_accounts,
+                            proposal_data.amounts[i],
+                            proposal_data.min_out[i],
+                            a_to_b,
+                        );
+                        (swap_ix, pool_accounts)
+                    }
+                    _ => return Err(FundError::UnsupportedDex.into()),
+                };
+                swap_account_infos.extend([
+                    token_program_info.clone(),
+                    vault_account_info.clone(),
+                    vault_from_ata_info.clone(),
+                    vault_to_ata_info.clone(),
+                    dex_program_info.clone(),
+                ]);
+
+                // Vault PDA signs as the owner of the source token account
+                assert_owned_by(vault_to_ata_info, &spl_token::id())?;
+                let to_amount_before = TokenAccount::unpack(&vault_to_ata_info.data.borrow())?.amount;
+                let from_amount_before = vault_from_data.amount;
+                invoke_signed(
+                    &swap_ix,
+                    &swap_account_infos,
+                    &[&[b"vault", fund_account_info.key.as_ref(), &[vault_bump]]],
+                )?;
+
+                // Route can't spend more of the From Asset than the leg's amount
+                let from_amount_after = TokenAccount::unpack(&vault_from_ata_info.data.borrow())?.amount;
+                if from_amount_before.saturating_sub(from_amount_after) > proposal_data.amounts[i] {
+                    return Err(FundError::SwapOverspent.into());
+                }
+
+                // Leg must deliver at least the minimum output agreed in the proposal, failing
+                // here reverts every leg of the execution
+                let to_amount_after = TokenAccount::unpack(&vault_to_ata_info.data.borrow())?.amount;
+                if to_amount_after.saturating_sub(to_amount_before) < proposal_data.min_out[i] {
+                    return Err(FundError::SlippageExceeded.into());
+                }
+
+                proposal_data.legs_executed |= 1 << i;
+            }
+
+            // Proposal stays open until its last leg is swapped
+            if routes.len() < pending_legs.len() {
+                proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+                msg!("Executed {} of {} pending legs", routes.len(), pending_legs.len());
+                return Ok(());
+            }
+        }
+        PROPOSAL_KIND_ADD_MEMBER => {
+            if fund_data.members.contains(&proposal_data.target_member) {
+                return Err(FundError::AlreadyMember.into());
+            }
+            if fund_data.members.len() >= MAX_FUND_MEMBERS {
+                return Err(FundError::FundFull.into());
+            }
+
+            // Grow the Fund account by one member slot, executor pays the extra rent
+            grow_account(fund_account_info, executor_account_info, system_program_info, 32)?;
+
+            fund_data.members.push(proposal_data.target_member);
+        }
+        PROPOSAL_KIND_SET_ROLES => {
+            let target_user_specific_info = next_account_info(accounts_iter)?; // Target member's User-specific PDA
+
+            let (target_user_specific_pda, _target_bump) = derive_user_specific_pda(program_id, fund_account_info.key, &proposal_data.target_member);
+            if *target_user_specific_info.key != target_user_specific_pda {
+                return Err(FundError::InvalidAccountData.into());
+            }
+            assert_owned_by(target_user_specific_info, program_id)?;
+            let mut target_user_data = UserSpecificAccount::try_from_slice(&target_user_specific_info.data.borrow())?;
+            if !target_user_data.is_initialized {
+                return Err(FundError::AccountNotInitialized.into());
+            }
+            target_user_data.roles = proposal_data.roles;
+            target_user_data.serialize(&mut &mut target_user_specific_info.data.borrow_mut()[..])?;
+        }
+        PROPOSAL_KIND_REMOVE_MEMBER => {
+            let removed_user_specific_info = next_account_info(accounts_iter)?; // Removed member's User-specific PDA
+            let removed_wallet_info = next_account_info(accounts_iter)?; // Removed member's Wallet
+
+            let (removed_user_specific_pda, _removed_user_specific_bump) = derive_user_specific_pda(program_id, fund_account_info.key, &proposal_data.target_member);
+            if *removed_user_specific_info.key != removed_user_specific_pda || *removed_wallet_info.key != proposal_data.target_member {
+                return Err(FundError::InvalidAccountData.into());
+            }
+
+            // Creator can't be voted out through a plain removal proposal
+            if proposal_data.target_member == fund_data.creator {
+                return Err(FundError::CannotRemoveCreator.into());
+            }
+            let position = fund_data.members
+                .iter()
+                .position(|key| *key == proposal_data.target_member)
+                .ok_or(FundError::MemberNotFound)?;
+            fund_data.members.remove(position);
+
+            // Shrink the Fund account, returning the freed rent to the removed member
+            let rent = Rent::get()?;
+            let fund_current_rent = fund_account_info.lamports();
+            let fund_new_size = fund_account_info.data_len() - 32;
+            let fund_new_rent = rent.minimum_balance(fund_new_size);
+            if fund_new_rent < fund_current_rent {
+                **fund_account_info.try_borrow_mut_lamports()? -= fund_current_rent - fund_new_rent;
+                **removed_wallet_info.try_borrow_mut_lamports()? += fund_current_rent - fund_new_rent;
+            }
+            fund_account_info.realloc(fund_new_size, false)?;
+
+            // Deactivate the member's Fund-specific account, their deposit stays withdrawable
+            assert_owned_by(removed_user_specific_info, program_id)?;
+            let mut removed_user_data = UserSpecificAccount::try_from_slice(&removed_user_specific_info.data.borrow())?;
+            if !removed_user_data.is_initialized {
+                return Err(FundError::AccountNotInitialized.into());
+            }
+            removed_user_data.is_active = false;
+            removed_user_data.serialize(&mut &mut removed_user_specific_info.data.borrow_mut()[..])?;
+        }
+        PROPOSAL_KIND_WITHDRAWAL => {
+            let vault_wsol_info = next_account_info(accounts_iter)?; // Vault's WSOL ATA
+            let recipient_wsol_info = next_account_info(accounts_iter)?; // Recipient's WSOL Token Account
+            let reserve_info = next_account_info(accounts_iter)?; // Fund's Reserve WSOL Account
+
+            let expected_vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+            if *vault_wsol_info.key != expected_vault_wsol {
+                return Err(FundError::InvalidTokenAccount.into());
+            }
+            assert_owned_by(recipient_wsol_info, &spl_token::id())?;
+            let recipient_wsol_data = TokenAccount::unpack(&recipient_wsol_info.data.borrow())?;
+            if recipient_wsol_data.mint != spl_token::native_mint::id() || recipient_wsol_data.owner != proposal_data.recipient {
+                return Err(FundError::InvalidTokenAccount.into());
+            }
+
+            // No self-dealing: the proposer can't be paid directly, nor through a recipient account
+            // they hold a delegation or close authority over
+            let proposer = proposal_data.proposer;
+            if proposal_data.recipient == proposer ||
+               recipient_wsol_data.delegate == COption::Some(proposer) ||
+               recipient_wsol_data.close_authority == COption::Some(proposer) {
+                return Err(FundError::InvalidProposalTarget.into());
+            }
+
+            // WSOL amounts already exclude the accounts' rent-exempt reserve
+            assert_owned_by(vault_wsol_info, &spl_token::id())?;
+            let vault_wsol_data = TokenAccount::unpack(&vault_wsol_info.data.borrow())?;
+            let reserve_balance = reserve_wsol_amount(program_id, fund_account_info.key, reserve_info)?;
+            if vault_wsol_data.amount.saturating_add(reserve_balance) < proposal_data.lamports {
+                return Err(FundError::InsufficientVaultFunds.into());
+            }
+
+            // Paid from the reserve first, like a member's withdrawal, vault PDA signs as owner
+            pay_out_wsol(
+                vault_account_info,
+                reserve_info,
+                reserve_balance,
+                vault_wsol_info,
+                recipient_wsol_info,
+                token_program_info,
+                &[b"vault", fund_account_info.key.as_ref(), &[vault_bump]],
+                proposal_data.lamports,
+            )?;
+
+            fund_data.total_deposit = fund_data.total_deposit.saturating_sub(proposal_data.lamports);
+
+            assert_owned_by(vault_account_info, program_id)?;
+            let mut vault_data = VaultAccount::try_from_slice(&vault_account_info.data.borrow())?;
+            if fresh_vault_value(&vault_data, current_time).is_some() {
+                vault_data.cached_value = vault_data.cached_value.saturating_sub(proposal_data.lamports);
+            }
+            vault_data.serialize(&mut &mut vault_account_info.data.borrow_mut()[..])?;
+        }
+        PROPOSAL_KIND_CONFIG_CHANGE => {
+            // Fund config may have changed since the proposal was created, so validate again
+            let new_config = apply_config_change(&fund_data.config, &proposal_data.config_change);
+            validate_fund_config(&new_config)?;
+
+            fund_data.name = proposal_data.config_change.name;
+            fund_data.is_private = proposal_data.config_change.is_private;
+            fund_data.config = new_config;
+        }
+        _ => return Err(FundError::InvalidInstruction.into()),
+    }
+
+    // A proposal that passed and ran was no spam, so its bond goes straight back to the proposer
+    if proposal_data.bond > 0 {
+        **proposal_account_info.try_borrow_mut_lamports()? -= proposal_data.bond;
+        **proposer_account_info.try_borrow_mut_lamports()? += proposal_data.bond;
+        proposal_data.bond = 0;
+    }
+
+    // Mark as executed so the proposal cannot be replayed
+    proposal_data.executed = true;
+    proposal_data.serialize(&mut &mut proposal_account_info.data.borrow_mut()[..])?;
+
+    // Reward the executor for cranking. Paid once, when the proposal becomes executed, out of the
+    // native lamports the Vault PDA holds above its rent (direct transfers).
+    // Vault's WSOL holdings back governance tokens and are never used for it.
+    let reward = fund_data.config.execution_reward.min(vault_spare_lamports(vault_account_info)?);
+    if reward > 0 {
+        transfer_from_vault(vault_account_info, executor_account_info, reward)?;
+    }
+
+    fund_data.active_proposals = fund_data.active_proposals.saturating_sub(1);
+    fund_data.serialize(&mut &mut fund_account_info.data.borrow_mut()[..])?;
+
+    msg!("Proposal executed successfully");
+    events::emit_execute(fund_account_info.key, proposal_account_info.key, executor_account_info.key, proposal_data.proposal_kind);
+
+    Ok(())
+}
+
+// Tally rules a proposal has to meet before it can be executed: more yes than no votes, yes votes
+// making up quorum_bps of the governance supply snapshotted at creation, and pass_threshold_bps
+// of the votes cast
+fn check_proposal_passes(proposal_data: &InvestmentProposalAccount, config: &FundConfig) -> ProgramResult {
+    if proposal_data.votes_yes <= proposal_data.votes_no {
+        return Err(FundError::ProposalNotPassed.into());
+    }
+
+    if (proposal_data.votes_yes as u128) * 10000 < (quorum_base(proposal_data, config) as u128) * (config.quorum_bps as u128) {
+        return Err(FundError::QuorumNotReached.into());
+    }
+
+    let votes_cast = (proposal_data.votes_yes as u128) + (proposal_data.votes_no as u128);
+    if (proposal_data.votes_yes as u128) * 10000 < votes_cast * (config.pass_threshold_bps as u128) {
+        return Err(FundError::ProposalNotPassed.into());
+    }
+
+    Ok(())
+}
+
+// Creator-only instructions carry the Fund's current admin_nonce, which moves on with every one
+// that succeeds
+fn consume_admin_nonce(fund_data: &mut FundAccount, nonce: u64) -> ProgramResult {
+    if nonce != fund_data.admin_nonce {
+        return Err(FundError::StaleNonce.into());
+    }
+    fund_data.admin_nonce = fund_data.admin_nonce.wrapping_add(1);
+
+    Ok(())
+}
+
+// Voting power quorum is measured against. Quadratic power is compared to the square root of the
+// supply, the least total power the supply's holders can cast between them.
+fn quorum_base(proposal_data: &InvestmentProposalAccount, config: &FundConfig) -> u64 {
+    if config.voting_mode == VOTING_MODE_QUADRATIC {
+        isqrt(proposal_data.snapshot_supply)
+    } else {
+        proposal_data.snapshot_supply
+    }
+}
+
+// Anti-sniping: a vote landing within the Fund's anti_snipe_window of the deadline pushes the
+// deadline out by deadline_extension, so a last-second swing can still be answered. Extensions
+// stop once the deadline is MAX_PROPOSAL_WINDOW past the proposal's creation.
+fn extend_deadline_if_sniped(proposal_data: &mut InvestmentProposalAccount, config: &FundConfig, current_time: i64) {
+    if config.anti_snipe_window == 0 || config.deadline_extension == 0 {
+        return;
+    }
+    if proposal_data.deadline.saturating_sub(current_time) > config.anti_snipe_window {
+        return;
+    }
+
+    let latest_deadline = proposal_data.created_at.saturating_add(MAX_PROPOSAL_WINDOW);
+    let extended = proposal_data.deadline.saturating_add(config.deadline_extension).min(latest_deadline);
+    if extended > proposal_data.deadline {
+        msg!("Late vote, deadline extended to {}", extended);
+        proposal_data.deadline = extended;
+    }
+}
+
+// Fund config with a config-change proposal's quorum and pass threshold applied
+fn apply_config_change(config: &FundConfig, config_change: &ConfigChange) -> FundConfig {
+    FundConfig {
+        quorum_bps: config_change.quorum_bps,
+        pass_threshold_bps: config_change.pass_threshold_bps,
+        dex_allowlist: config_change.dex_allowlist,
+        ..config.clone()
+    }
+}
+
+// Grows a program-owned account by `additional_bytes`, with `payer` topping up its lamports so it
+// stays rent-exempt at the new size. The runtime lets an account grow by at most
+// MAX_PERMITTED_DATA_INCREASE (10 KiB) over its size at the start of the instruction, so larger
+// growth has to be spread over several instructions. A Fund at MAX_FUND_MEMBERS stays far below it.
+fn grow_account<'a>(
+    account_info: &AccountInfo<'a>,
+    payer_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    additional_bytes: usize,
+) -> ProgramResult {
+    if additional_bytes > MAX_PERMITTED_DATA_INCREASE {
+        return Err(ProgramError::InvalidRealloc);
+    }
+
+    let new_size = account_info.data_len() + additional_bytes;
+    let new_min_balance = Rent::get()?.minimum_balance(new_size);
+    let current_balance = account_info.lamports();
+    if new_min_balance > current_balance {
+        invoke(
+            &system_instruction::transfer(
+                payer_account_info.key,
+                account_info.key,
+                new_min_balance - current_balance,
+            ),
+            &[payer_account_info.clone(), account_info.clone(), system_program_info.clone()],
+        )?;
+    }
+    account_info.realloc(new_size, false)?;
+
+    Ok(())
+}
+
+// Creates the Vote PDA of `owner` on `proposal`, paid by `payer`
+fn create_vote_pda<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    vote_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    proposal: &Pubkey,
+    owner: &Pubkey,
+) -> ProgramResult {
+    let (vote_pda, vote_bump) = Pubkey::find_program_address(&[b"vote", proposal.as_ref(), owner.as_ref()], program_id);
+    if *vote_account_info.key != vote_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let rent = Rent::get()?;
+    let vote_space = VoteAccount::LEN;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_account_info.key,
+            vote_account_info.key,
+            rent.minimum_balance(vote_space),
+            vote_space as u64,
+            program_id
+        ),
+        &[
+            payer_account_info.clone(),
+            vote_account_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[b"vote", proposal.as_ref(), owner.as_ref(), &[vote_bump]]]
+    )?;
+
+    Ok(())
+}
+
+// Adds a member's voting power to the proposal's tally and returns it.
+// Voting power is weighted by the governance token balance recorded in the member's program-owned
+// User-specific PDA, not by their token account. Governance tokens can be transferred freely, so
+// reading the SPL balance would let a member sell their tokens to an outsider who then votes; the
+// recorded balance only moves on deposit and withdrawal, which makes the stake soulbound.
+// It is measured against the supply snapshot taken at proposal creation. Members who joined after
+// the snapshot have no power on the proposal. Those who deposited since vote with the balance they
+// held before those deposits, less anything withdrawn, so topping up can't add to a stake already
+// counted. Funds using time-weighted voting scale the stake by the member's tenure.
+fn tally_vote(
+    proposal_data: &mut InvestmentProposalAccount,
+    user_data: &UserSpecificAccount,
+    config: &FundConfig,
+    current_time: i64,
+    vote: u8,
+) -> Result<u64, ProgramError> {
+    let stake = if user_data.join_time > proposal_data.created_at {
+        0
+    } else if user_data.last_deposit_time > proposal_data.created_at {
+        user_data.pre_deposit_balance.min(user_data.governance_token_balance)
+    } else {
+        user_data.governance_token_balance
+    };
+    let voting_power = match config.voting_mode {
+        VOTING_MODE_TIME_WEIGHTED => time_weighted_power(stake, user_data.join_time, current_time, config.time_weight_cap_seconds),
+        VOTING_MODE_QUADRATIC => isqrt(stake),
+        _ => stake,
+    };
+
+    if vote == 1 {
+        proposal_data.votes_yes = proposal_data.votes_yes.checked_add(voting_power).ok_or(FundError::ArithmeticOverflow)?;
+    } else {
+        proposal_data.votes_no = proposal_data.votes_no.checked_add(voting_power).ok_or(FundError::ArithmeticOverflow)?;
+    }
+
+    Ok(voting_power)
+}
+
+// Makes `member` an active member in their Fund-specific PDA with `roles`, creating the PDA first
+// if InitUserAccount hasn't
+fn create_user_specific_pda<'a>(
+    program_id: &Pubkey,
+    member_wallet_info: &'a AccountInfo<'a>,
+    system_program_info: &'a AccountInfo<'a>,
+    fund_account_info: &'a AccountInfo<'a>,
+    user_specific_info: &'a AccountInfo<'a>,
+    roles: u8,
+) -> ProgramResult {
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let (user_specific_pda, _user_specific_bump) = derive_user_specific_pda(program_id, fund_account_info.key, member_wallet_info.key);
+
+    if *user_specific_info.key != user_specific_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    if user_specific_info.data_is_empty() {
+        create_user_specific_account(program_id, member_wallet_info, system_program_info, fund_account_info, user_specific_info)?;
+    }
+
+    assert_owned_by(user_specific_info, program_id)?;
+    let mut user_data = UserSpecificAccount::try_from_slice(&user_specific_info.data.borrow())?;
+    if !user_data.is_initialized || user_data.fund != *fund_account_info.key {
+        return Err(FundError::AccountNotInitialized.into());
+    }
+    if user_data.is_active {
+        msg!("User already exists");
+        return Ok(());
+    }
+
+    // Membership starts now, whenever the account itself was created
+    user_data.is_active = true;
+    user_data.join_time = current_time;
+    user_data.roles = roles;
+    user_data.serialize(&mut &mut user_specific_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Creates `member`'s Fund-specific PDA, recording the Fund and the member but not yet active in it
+fn create_user_specific_account<'a>(
+    program_id: &Pubkey,
+    member_wallet_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    fund_account_info: &AccountInfo<'a>,
+    user_specific_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let (user_specific_pda, user_specific_bump) = derive_user_specific_pda(program_id, fund_account_info.key, member_wallet_info.key);
+    if *user_specific_info.key != user_specific_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let rent = Rent::get()?;
+    let size = UserSpecificAccount::LEN;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            member_wallet_info.key,
+            user_specific_info.key,
+            rent.minimum_balance(size),
+            size as u64,
+            program_id
+        ),
+        &[member_wallet_info.clone(),user_specific_info.clone(),system_program_info.clone()],
+        &[&[USER_SEED, fund_account_info.key.as_ref(), member_wallet_info.key.as_ref(),&[user_specific_bump]]]
+    )?;
+
+    let mut user_data= UserSpecificAccount::try_from_slice(&user_specific_info.data.borrow())?;
+
+        user_data.version = USER_SPECIFIC_ACCOUNT_VERSION;
+        user_data.fund = *fund_account_info.key;
+        user_data.is_active = false;
+        user_data.join_time = current_time;
+        user_data.pubkey = *member_wallet_info.key;
+        user_data.is_initialized = true;
+        user_data.pre_deposit_balance = u64::MAX;
+
+    user_data.serialize(&mut &mut user_specific_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_proposal_pda<'a>(
+    program_id: &Pubkey,
+    proposer_account_info: &AccountInfo<'a>,
+    fund_account_info: &AccountInfo<'a>,
+    proposal_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    index: u8,
+    proposal_space: usize,
+    bond: u64,
+) -> ProgramResult {
+    let (proposal_pda, proposal_bump) = Pubkey::find_program_address(
+        &[
+            b"proposal-investment",
+            proposer_account_info.key.as_ref(),
+            &[index],
+            fund_account_info.key.as_ref()
+        ],
+        program_id
+    );
+    if *proposal_account_info.key != proposal_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    // Rent Calculation, the proposer's bond is escrowed in the account on top of it
+    let rent = Rent::get()?;
+    let total_rent = rent.minimum_balance(proposal_space);
+    let total_lamports = total_rent.checked_add(bond).ok_or(FundError::ArithmeticOverflow)?;
+
+    // Create Proposal Account
+    invoke_signed(
+        &system_instruction::create_account(
+            proposer_account_info.key,
+            proposal_account_info.key,
+            total_lamports,
+            proposal_space as u64,
+            program_id
+        ),
+        &[
+            proposal_account_info.clone(),
+            proposer_account_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            b"proposal-investment",
+            proposer_account_info.key.as_ref(),
+            &[index],
+            fund_account_info.key.as_ref(),
+            &[proposal_bump]
+        ]]
+    )?;
+
+    Ok(())
+}
+
+// Adds `proposal` to the Fund's proposal registry, creating the registry with the Fund's first
+// proposal. `payer` covers the creation and the 32 bytes every entry adds.
+fn register_proposal<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    registry_account_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    fund_pda: &Pubkey,
+    proposal: &Pubkey,
+) -> ProgramResult {
+    let (registry_pda, registry_bump) = derive_proposal_registry_pda(program_id, fund_pda);
+    if *registry_account_info.key != registry_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    let mut registry_data = if registry_account_info.data_is_empty() {
+        let registry_space = ProposalRegistry::space(0);
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_account_info.key,
+                registry_account_info.key,
+                Rent::get()?.minimum_balance(registry_space),
+                registry_space as u64,
+                program_id,
+            ),
+            &[payer_account_info.clone(), registry_account_info.clone(), system_program_info.clone()],
+            &[&[PROPOSAL_REGISTRY_SEED, fund_pda.as_ref(), &[registry_bump]]],
+        )?;
+        ProposalRegistry {
+            fund: *fund_pda,
+            proposals: vec![],
+        }
+    } else {
+        assert_owned_by(registry_account_info, program_id)?;
+        ProposalRegistry::try_from_slice(&registry_account_info.data.borrow())?
+    };
+
+    grow_account(registry_account_info, payer_account_info, system_program_info, 32)?;
+    registry_data.proposals.push(*proposal);
+    registry_data.serialize(&mut &mut registry_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Drops `proposal` from the Fund's proposal registry and shrinks it to fit. Proposals created
+// before the registry existed were never listed, so a missing entry is not an error.
+fn unregister_proposal(
+    program_id: &Pubkey,
+    registry_account_info: &AccountInfo,
+    fund_pda: &Pubkey,
+    proposal: &Pubkey,
+) -> ProgramResult {
+    let (registry_pda, _registry_bump) = derive_proposal_registry_pda(program_id, fund_pda);
+    if *registry_account_info.key != registry_pda {
+        return Err(FundError::InvalidAccountData.into());
+    }
+    if registry_account_info.data_is_empty() {
+        return Ok(());
+    }
+
+    assert_owned_by(registry_account_info, program_id)?;
+    let mut registry_data = ProposalRegistry::try_from_slice(&registry_account_info.data.borrow())?;
+    if let Some(index) = registry_data.proposals.iter().position(|p| p == proposal) {
+        registry_data.proposals.swap_remove(index);
+        registry_account_info.realloc(ProposalRegistry::space(registry_data.proposals.len()), false)?;
+        registry_data.serialize(&mut &mut registry_account_info.data.borrow_mut()[..])?;
+    }
+
+    Ok(())
+}
+
+// Creates the Fund's reserve, a WSOL token account at the reserve PDA owned by the Vault PDA. The
+// vault signs for it like for its ATAs, but investment legs only ever touch ATAs.
+#[allow(clippy::too_many_arguments)]
+fn create_reserve_account<'a>(
+    program_id: &Pubkey,
+    payer_account_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    vault_pda: &Pubkey,
+    fund_pda: &Pubkey,
+    wsol_mint_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (reserve_pda, reserve_bump) = derive_reserve_pda(program_id, fund_pda);
+    if *reserve_info.key != reserve_pda {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_account_info.key,
+            reserve_info.key,
+            Rent::get()?.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            token_program_info.key,
+        ),
+        &[
+            payer_account_info.clone(),
+            reserve_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[RESERVE_SEED, fund_pda.as_ref(), &[reserve_bump]]],
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_account3(
+            token_program_info.key,
+            reserve_info.key,
+            wsol_mint_info.key,
+            vault_pda,
+        )?,
+        &[
+            reserve_info.clone(),
+            wsol_mint_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+// WSOL held in the Fund's reserve account, zero until the first reserve share creates it
+fn reserve_wsol_amount(program_id: &Pubkey, fund_pda: &Pubkey, reserve_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let (reserve_pda, _reserve_bump) = derive_reserve_pda(program_id, fund_pda);
+    if *reserve_info.key != reserve_pda {
+        return Err(FundError::InvalidTokenAccount.into());
+    }
+    if reserve_info.data_is_empty() {
+        return Ok(0);
+    }
+    assert_owned_by(reserve_info, &spl_token::id())?;
+    Ok(TokenAccount::unpack(&reserve_info.data.borrow())?.amount)
+}
+
+// Pays `amount` of the vault's WSOL to `destination_info`, drawing on the reserve's
+// `reserve_balance` first and the vault's WSOL ATA for the rest. Vault PDA signs for both.
+#[allow(clippy::too_many_arguments)]
+fn pay_out_wsol<'a>(
+    vault_account_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    reserve_balance: u64,
+    vault_wsol_info: &AccountInfo<'a>,
+    destination_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    vault_seeds: &[&[u8]],
+    amount: u64,
+) -> ProgramResult {
+    let from_reserve = amount.min(reserve_balance);
+    for (source_info, source_amount) in [(reserve_info, from_reserve), (vault_wsol_info, amount - from_reserve)] {
+        if source_amount == 0 {
+            continue;
+        }
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_info.key,
+                destination_info.key,
+                vault_account_info.key,
+                &[],
+                source_amount,
+            )?,
+            &[
+                source_info.clone(),
+                destination_info.clone(),
+                vault_account_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[vault_seeds],
+        )?;
+    }
+
+    Ok(())
+}
+
+// Lamport value of the vault's non-WSOL holdings. Expects the Pyth SOL/USD feed followed by
+// (Vault ATA, Mint, Pyth price feed) for every accepted mint other than WSOL, in accepted_mints
+// order, so no holding can be left out. Feeds must be the ones bound to the mints and no older
+// than the Fund's max_price_age. A Fund accepting only WSOL takes no accounts and values to zero.
+fn value_token_holdings(
+    fund_data: &FundAccount,
+    vault_pda: &Pubkey,
+    accounts_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<u64, ProgramError> {
+    let token_mints: Vec<&AcceptedMint> = fund_data.accepted_mints
+        .iter()
+        .filter(|accepted| accepted.mint != spl_token::native_mint::id())
+        .collect();
+    if token_mints.is_empty() {
+        return Ok(0);
+    }
+
+    let sol_feed_info = next_account_info(accounts_iter)?; // Pyth SOL/USD price feed
+    if *sol_feed_info.key != SOL_USD_PRICE_FEED {
+        return Err(FundError::InvalidOracleAccount.into());
+    }
+    let sol_price = read_price(sol_feed_info, fund_data.config.max_price_age)?;
+
+    let mut holdings_value: u64 = 0;
+    for accepted in token_mints {
+        let token_account_info = next_account_info(accounts_iter)?; // Vault's ATA for the mint
+        let mint_account_info = next_account_info(accounts_iter)?; // Holding's Mint
+        let price_feed_info = next_account_info(accounts_iter)?; // Holding's Pyth price feed
+
+        if *mint_account_info.key != accepted.mint ||
+           *token_account_info.key != spl_associated_token_account::get_associated_token_address(vault_pda, &accepted.mint) {
+            return Err(FundError::InvalidTokenAccount.into());
+        }
+        if *price_feed_info.key != accepted.price_feed {
+            return Err(FundError::InvalidOracleAccount.into());
+        }
+
+        // An ATA never created or emptied holds nothing to value
+        if token_account_info.data_is_empty() {
+            continue;
+        }
+        assert_owned_by(token_account_info, &spl_token::id())?;
+        let amount = TokenAccount::unpack(&token_account_info.data.borrow())?.amount;
+        if amount == 0 {
+            continue;
+        }
+        assert_owned_by(mint_account_info, &spl_token::id())?;
+        let decimals = spl_token::state::Mint::unpack(&mint_account_info.data.borrow())?.decimals;
+        let token_price = read_price(price_feed_info, fund_data.config.max_price_age)?;
+        let token_value = token_value_in_lamports(amount, decimals, token_price, sol_price)?;
+        holdings_value = holdings_value.checked_add(token_value).ok_or(FundError::ArithmeticOverflow)?;
+    }
+
+    Ok(holdings_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::{
+        entrypoint::{deserialize, BPF_ALIGN_OF_U128, NON_DUP_MARKER},
+        instruction::Instruction,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        program_utils::limited_deserialize,
+        system_instruction::SystemInstruction,
+    };
+    use crate::{dex::SUPPORTED_DEX_MASK, oracle::{tests::price_account, PYTH_PROGRAM_ID}, utils::VAULT_VALUE_MAX_AGE};
+    use spl_token::state::{AccountState, Mint};
+    use std::{cell::Cell, collections::HashMap, sync::Once};
+
+    thread_local! {
+        static NOW: Cell<i64> = const { Cell::new(0) };
+        // WSOL the mocked Jupiter route takes from its source and output it pays to its destination
+        static SWAP: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+    }
+
+    // Syscalls a test transaction needs: the clock and rent sysvars, and CPIs into the system and
+    // token programs, which run in-process against the caller's AccountInfos. Jupiter is mocked.
+    struct TestSyscalls;
+
+    impl SyscallStubs for TestSyscalls {
+        fn sol_log(&self, _message: &str) {}
+
+        fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock { unix_timestamp: NOW.with(|now| now.get()), ..Clock::default() };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], _signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+            // PDA signatures are taken on trust, the program under test derives its own seeds
+            let mut infos = Vec::with_capacity(instruction.accounts.len());
+            for meta in &instruction.accounts {
+                let mut info = account_infos.iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?
+                    .clone();
+                info.is_signer |= meta.is_signer;
+                infos.push(info);
+            }
+
+            if instruction.program_id == system_program::id() {
+                process_system_instruction(&infos, &instruction.data)
+            } else if instruction.program_id == spl_token::id() {
+                spl_token::processor::Processor::process(&instruction.program_id, &infos, &instruction.data)
+            } else if instruction.program_id == JUPITER_PROGRAM_ID {
+                process_mock_swap(&infos)
+            } else {
+                Err(ProgramError::IncorrectProgramId)
+            }
+        }
+    }
+
+    fn process_system_instruction(infos: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let transfer = |from: &AccountInfo, to: &AccountInfo, lamports: u64| -> ProgramResult {
+            if !from.is_signer || from.lamports() < lamports {
+                return Err(ProgramError::InsufficientFunds);
+            }
+            **from.try_borrow_mut_lamports()? -= lamports;
+            **to.try_borrow_mut_lamports()? += lamports;
+            Ok(())
+        };
+
+        match limited_deserialize(data, 1024).map_err(|_| ProgramError::InvalidInstructionData)? {
+            SystemInstruction::CreateAccount { lamports, space, owner } => {
+                if infos[1].lamports() != 0 || !infos[1].data_is_empty() {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                transfer(&infos[0], &infos[1], lamports)?;
+                infos[1].realloc(space as usize, true)?;
+                infos[1].assign(&owner);
+                Ok(())
+            }
+            SystemInstruction::Transfer { lamports } => transfer(&infos[0], &infos[1], lamports),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // Moves SWAP's amounts out of the route's source token account and into its destination
+    fn process_mock_swap(infos: &[AccountInfo]) -> ProgramResult {
+        let (spent, received) = SWAP.with(|swap| swap.get());
+        let mut source = TokenAccount::unpack(&infos[2].data.borrow())?;
+        let mut destination = TokenAccount::unpack(&infos[3].data.borrow())?;
+        source.amount = source.amount.checked_sub(spent).ok_or(ProgramError::InsufficientFunds)?;
+        destination.amount += received;
+        TokenAccount::pack(source, &mut infos[2].data.borrow_mut())?;
+        TokenAccount::pack(destination, &mut infos[3].data.borrow_mut())
+    }
+
+    struct TestAccount {
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        executable: bool,
+    }
+
+    // Accounts that persist between the instructions of one test, like a tiny ledger
+    struct TestLedger {
+        program_id: Pubkey,
+        accounts: HashMap<Pubkey, TestAccount>,
+    }
+
+    impl TestLedger {
+        fn new() -> Self {
+            static STUBS: Once = Once::new();
+            STUBS.call_once(|| {
+                set_syscall_stubs(Box::new(TestSyscalls));
+            });
+
+            let mut ledger = TestLedger { program_id: Pubkey::new_unique(), accounts: HashMap::new() };
+            for program in [system_program::id(), spl_token::id(), spl_associated_token_account::id(), JUPITER_PROGRAM_ID] {
+                ledger.accounts.insert(program, TestAccount { owner: Pubkey::default(), lamports: 1, data: vec![], executable: true });
+            }
+            ledger
+        }
+
+        fn set_account(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+            let lamports = Rent::default().minimum_balance(data.len()).max(1);
+            self.accounts.insert(key, TestAccount { owner, lamports, data, executable: false });
+        }
+
+        fn fund_wallet(&mut self, key: Pubkey, lamports: u64) {
+            self.accounts.insert(key, TestAccount { owner: system_program::id(), lamports, data: vec![], executable: false });
+        }
+
+        fn data(&self, key: &Pubkey) -> &[u8] {
+            self.accounts.get(key).map(|account| account.data.as_slice()).unwrap_or(&[])
+        }
+
+        // Runs one instruction against the ledger in the SBF input layout, so reallocs behave as on
+        // chain. Account changes are kept only if it succeeds.
+        fn process(&mut self, metas: &[(Pubkey, bool, bool)], data: &[u8], now: i64) -> ProgramResult {
+            NOW.with(|cell| cell.set(now));
+
+            let mut input: Vec<u8> = Vec::new();
+            input.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+            for (i, (key, is_signer, is_writable)) in metas.iter().enumerate() {
+                if let Some(first) = metas[..i].iter().position(|(other, _, _)| other == key) {
+                    input.push(first as u8);
+                    input.extend_from_slice(&[0; 7]);
+                    continue;
+                }
+                let empty = TestAccount { owner: system_program::id(), lamports: 0, data: vec![], executable: false };
+                let account = self.accounts.get(key).unwrap_or(&empty);
+                input.extend_from_slice(&[NON_DUP_MARKER, *is_signer as u8, *is_writable as u8, account.executable as u8, 0, 0, 0, 0]);
+                input.extend_from_slice(key.as_ref());
+                input.extend_from_slice(account.owner.as_ref());
+                input.extend_from_slice(&account.lamports.to_le_bytes());
+                input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+                input.extend_from_slice(&account.data);
+                input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+                input.resize(input.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+                input.extend_from_slice(&0_u64.to_le_bytes());
+            }
+            input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            input.extend_from_slice(data);
+            input.extend_from_slice(self.program_id.as_ref());
+
+            // deserialize expects the input 8-byte aligned
+            let mut aligned = vec![0_u64; input.len().div_ceil(8)];
+            let aligned_bytes = unsafe { std::slice::from_raw_parts_mut(aligned.as_mut_ptr() as *mut u8, input.len()) };
+            aligned_bytes.copy_from_slice(&input);
+            let (program_id, accounts, instruction_data) = unsafe { deserialize(aligned.as_mut_ptr() as *mut u8) };
+
+            process_instruction(program_id, &accounts, instruction_data)?;
+
+            for info in accounts.iter() {
+                self.accounts.insert(*info.key, TestAccount {
+                    owner: *info.owner,
+                    lamports: info.lamports(),
+                    data: info.data.borrow().to_vec(),
+                    executable: info.executable,
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn test_config() -> FundConfig {
+        FundConfig {
+            quorum_bps: 5000,
+            pass_threshold_bps: 5000,
+            governance_decimals: 9,
+            max_total_deposit: 0,
+            max_member_deposit: 0,
+            lockup_seconds: 0,
+            min_proposal_deposit: 0,
+            deposit_fee_bps: 0,
+            fee_collector: Pubkey::default(),
+            proposal_bond: 0,
+            voting_mode: 0,
+            time_weight_cap_seconds: 0,
+            dex_allowlist: 0,
+            min_voting_period: DEFAULT_MIN_VOTING_PERIOD,
+            anti_snipe_window: 0,
+            deadline_extension: 0,
+            execution_reward: 0,
+            execution_delay: 0,
+            reserve_bps: 0,
+            max_open_proposals_per_member: 0,
+            max_price_age: DEFAULT_MAX_PRICE_AGE,
+        }
+    }
+
+    fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+        let is_native = if mint == spl_token::native_mint::id() {
+            COption::Some(Rent::default().minimum_balance(TokenAccount::LEN))
+        } else {
+            COption::None
+        };
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            is_native,
+            ..TokenAccount::default()
+        }.pack_into_slice(&mut data);
+        data
+    }
+
+    fn mint(authority: Pubkey, supply: u64) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::Some(authority),
+            supply,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }.pack_into_slice(&mut data);
+        data
+    }
+
+    // Public Fund with no members yet, holding nothing, plus its vault's WSOL account
+    fn public_fund(ledger: &mut TestLedger, fund_name: &str, config: FundConfig) -> Pubkey {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+
+        let fund_data = FundAccount {
+            version: FUND_ACCOUNT_VERSION,
+            name: [0; 32],
+            creator: Pubkey::new_unique(),
+            members: vec![],
+            total_deposit: 0,
+            governance_mint,
+            vault: vault_pda,
+            is_initialized: true,
+            created_at: 0,
+            is_private: 0,
+            is_paused: false,
+            deposits_frozen: false,
+            admin_nonce: 0,
+            active_proposals: 0,
+            last_proposal_at: 0,
+            config,
+            accepted_mints: vec![AcceptedMint { mint: spl_token::native_mint::id(), price_feed: SOL_USD_PRICE_FEED }],
+        };
+        let vault_data = VaultAccount {
+            version: VAULT_ACCOUNT_VERSION,
+            fund: fund_pda,
+            last_deposit_time: 0,
+            cached_value: 0,
+            last_valuation_time: 0,
+        };
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+        ledger.set_account(vault_pda, program_id, borsh::to_vec(&vault_data).unwrap());
+        ledger.set_account(governance_mint, spl_token::id(), mint(fund_pda, 0));
+        ledger.set_account(spl_token::native_mint::id(), spl_token::id(), mint(Pubkey::default(), 0));
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+        ledger.set_account(vault_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), vault_pda, 0));
+
+        fund_pda
+    }
+
+    // Funded wallet that ran InitUserAccount for the Fund and holds empty WSOL and governance token
+    // accounts, returned with its WSOL account
+    fn new_depositor(ledger: &mut TestLedger, fund_name: &str, now: i64) -> (Pubkey, Pubkey) {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let member = Pubkey::new_unique();
+        ledger.fund_wallet(member, 10_000_000_000);
+
+        let mut data = vec![7, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&[
+            (member, true, true),
+            (derive_user_pda(&program_id, &member).0, false, true),
+            (system_program::id(), false, false),
+            (fund_pda, false, false),
+            (derive_user_specific_pda(&program_id, &fund_pda, &member).0, false, true),
+        ], &data, now).unwrap();
+
+        let member_wsol = Pubkey::new_unique();
+        ledger.set_account(member_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), member, 0));
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        ledger.set_account(member_governance, spl_token::id(), token_account(governance_mint, member, 0));
+        (member, member_wsol)
+    }
+
+    // Accounts of a WSOL deposit by `member`, whose User-specific PDA must already exist
+    fn deposit_sol_accounts(program_id: &Pubkey, fund_name: &str, member: Pubkey, member_wsol: Pubkey) -> Vec<(Pubkey, bool, bool)> {
+        let (fund_pda, _) = derive_fund_pda(program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(program_id, &fund_pda);
+        let (user_specific_pda, _) = derive_user_specific_pda(program_id, &fund_pda, &member);
+        vec![
+            (member, true, true),
+            (member_wsol, false, true),
+            (vault_pda, false, true),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+            (spl_token::native_mint::id(), false, false),
+            (spl_token::id(), false, false),
+            (spl_associated_token_account::id(), false, false),
+            (fund_pda, false, true),
+            (user_specific_pda, false, true),
+            (system_program::id(), false, false),
+            (solana_program::sysvar::rent::id(), false, false),
+            (spl_associated_token_account::get_associated_token_address(&member, &governance_mint), false, true),
+            (governance_mint, false, true),
+            (derive_reserve_pda(program_id, &fund_pda).0, false, true),
+        ]
+    }
+
+    fn deposit_data(amount: u64, fund_name: &str) -> Vec<u8> {
+        let mut data = vec![8];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        data
+    }
+
+    // Deposits `amount` lamports of SOL from `member`, whose User-specific PDA must already exist
+    fn deposit_sol(ledger: &mut TestLedger, fund_name: &str, member: Pubkey, member_wsol: Pubkey, amount: u64, now: i64) -> ProgramResult {
+        let metas = deposit_sol_accounts(&ledger.program_id, fund_name, member, member_wsol);
+        ledger.process(&metas, &deposit_data(amount, fund_name), now)
+    }
+
+    // Withdraws `amount` lamports of the vault's SOL to `member`'s WSOL account
+    fn withdraw_sol(ledger: &mut TestLedger, fund_name: &str, member: Pubkey, member_wsol: Pubkey, amount: u64, now: i64) -> ProgramResult {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+
+        let mut data = vec![12];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&[
+            (member, true, true),
+            (member_wsol, false, true),
+            (vault_pda, false, true),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+            (fund_pda, false, true),
+            (derive_user_specific_pda(&program_id, &fund_pda, &member).0, false, true),
+            (governance_mint, false, true),
+            (spl_associated_token_account::get_associated_token_address(&member, &governance_mint), false, true),
+            (spl_token::id(), false, false),
+            (derive_reserve_pda(&program_id, &fund_pda).0, false, true),
+        ], &data, now)
+    }
+
+    // Creates `proposer`'s next proposal from instruction `data`, with `extra` accounts after the
+    // ones every proposal takes, and returns its address
+    fn propose(ledger: &mut TestLedger, fund_name: &str, proposer: Pubkey, data: &[u8], extra: &[(Pubkey, bool, bool)], now: i64) -> Result<Pubkey, ProgramError> {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &proposer);
+        let index = UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).map_or(0, |user_data| user_data.num_proposals);
+        let (proposal, _) = Pubkey::find_program_address(&[b"proposal-investment", proposer.as_ref(), &[index], fund_pda.as_ref()], &program_id);
+
+        let mut metas = vec![
+            (proposer, true, true),
+            (user_specific_pda, false, true),
+            (fund_pda, false, true),
+            (proposal, false, true),
+            (system_program::id(), false, false),
+            (derive_governance_mint_pda(&program_id, &fund_pda).0, false, false),
+            (derive_proposal_registry_pda(&program_id, &fund_pda).0, false, true),
+        ];
+        metas.extend_from_slice(extra);
+        ledger.process(&metas, data, now).map(|()| proposal)
+    }
+
+    // Proposal to add, remove or set the roles of `target`
+    fn propose_membership(ledger: &mut TestLedger, fund_name: &str, proposer: Pubkey, proposal_kind: u8, target: Pubkey, deadline: i64, now: i64) -> Result<Pubkey, ProgramError> {
+        let mut data = vec![13, proposal_kind];
+        data.extend_from_slice(target.as_ref());
+        data.extend_from_slice(&deadline.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        propose(ledger, fund_name, proposer, &data, &[], now)
+    }
+
+    fn vote_accounts(program_id: &Pubkey, fund_name: &str, voter: Pubkey, proposal: Pubkey) -> Vec<(Pubkey, bool, bool)> {
+        let (fund_pda, _) = derive_fund_pda(program_id, fund_name.as_bytes());
+        let (governance_mint, _) = derive_governance_mint_pda(program_id, &fund_pda);
+        vec![
+            (voter, true, true),
+            (Pubkey::find_program_address(&[b"vote", proposal.as_ref(), voter.as_ref()], program_id).0, false, true),
+            (proposal, false, true),
+            (system_program::id(), false, false),
+            (derive_user_specific_pda(program_id, &fund_pda, &voter).0, false, false),
+            (fund_pda, false, false),
+            (governance_mint, false, false),
+            (spl_associated_token_account::get_associated_token_address(&voter, &governance_mint), false, false),
+        ]
+    }
+
+    // Casts `voter`'s first vote on `proposal`, 1 for yes and 0 for no
+    fn cast_vote(ledger: &mut TestLedger, fund_name: &str, voter: Pubkey, proposal: Pubkey, vote: u8, now: i64) -> ProgramResult {
+        let metas = vote_accounts(&ledger.program_id, fund_name, voter, proposal);
+        let mut data = vec![3, vote, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&metas, &data, now)
+    }
+
+    // Fund whose only member holds 2 SOL, allowed to buy a second accepted mint on Jupiter,
+    // returned with the member and that mint
+    fn investing_fund(ledger: &mut TestLedger, fund_name: &str) -> (Pubkey, Pubkey) {
+        let program_id = ledger.program_id;
+        let fund_pda = public_fund(ledger, fund_name, FundConfig { dex_allowlist: SUPPORTED_DEX_MASK, ..test_config() });
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (member, member_wsol) = new_depositor(ledger, fund_name, 1_000);
+        deposit_sol(ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+
+        let to_mint = Pubkey::new_unique();
+        ledger.set_account(to_mint, spl_token::id(), mint(Pubkey::new_unique(), 0));
+        let vault_to_ata = spl_associated_token_account::get_associated_token_address(&vault_pda, &to_mint);
+        ledger.set_account(vault_to_ata, spl_token::id(), token_account(to_mint, vault_pda, 0));
+        let mut fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        fund_data.accepted_mints.push(AcceptedMint { mint: to_mint, price_feed: Pubkey::new_unique() });
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+
+        (member, to_mint)
+    }
+
+    // Single-leg proposal to swap `amount` of the vault's WSOL for at least `min_out` of `to_mint`
+    // on Jupiter
+    #[allow(clippy::too_many_arguments)]
+    fn propose_swap(ledger: &mut TestLedger, fund_name: &str, proposer: Pubkey, to_mint: Pubkey, amount: u64, min_out: u64, deadline: i64, now: i64) -> Result<Pubkey, ProgramError> {
+        let mut data = vec![2, PROPOSAL_KIND_INVESTMENT, 1];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&min_out.to_le_bytes());
+        data.push(DEX_JUPITER);
+        data.extend_from_slice(&deadline.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        propose(ledger, fund_name, proposer, &data, &[(spl_token::native_mint::id(), false, false), (to_mint, false, false)], now)
+    }
+
+    // Accounts every Execute takes, ahead of the ones for the proposal's kind
+    fn execute_accounts(ledger: &mut TestLedger, fund_name: &str, proposal: Pubkey) -> Vec<(Pubkey, bool, bool)> {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let executor = Pubkey::new_unique();
+        ledger.fund_wallet(executor, 1_000_000_000);
+        vec![
+            (executor, true, true),
+            (proposal, false, true),
+            (fund_pda, false, true),
+            (Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id).0, false, true),
+            (derive_governance_mint_pda(&program_id, &fund_pda).0, false, false),
+            (system_program::id(), false, false),
+            (spl_token::id(), false, false),
+            (InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().proposer, false, true),
+        ]
+    }
+
+    fn execute_data(proposal: Pubkey, routes: Vec<SwapRoute>) -> Vec<u8> {
+        let mut data = vec![11];
+        data.extend_from_slice(proposal.as_ref());
+        if !routes.is_empty() {
+            data.extend(borsh::to_vec(&routes).unwrap());
+        }
+        data
+    }
+
+    // Executes a single-leg swap proposal through the mocked Jupiter route, which takes `spent`
+    // WSOL and pays out `received` of `to_mint`, with `route_accounts` handed to the route
+    #[allow(clippy::too_many_arguments)]
+    fn execute_swap(ledger: &mut TestLedger, fund_name: &str, proposal: Pubkey, to_mint: Pubkey, spent: u64, received: u64, route_accounts: &[(Pubkey, bool, bool)], now: i64) -> ProgramResult {
+        let (fund_pda, _) = derive_fund_pda(&ledger.program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &ledger.program_id);
+        let mut metas = execute_accounts(ledger, fund_name, proposal);
+        metas.extend([
+            (spl_associated_token_account::id(), false, false),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &to_mint), false, true),
+            (to_mint, false, false),
+            (JUPITER_PROGRAM_ID, false, false),
+            (JUPITER_EVENT_AUTHORITY, false, false),
+        ]);
+        metas.extend_from_slice(route_accounts);
+        let route = SwapRoute { quoted_out_amount: received, slippage_bps: 50, num_accounts: route_accounts.len() as u8, route_plan: vec![] };
+
+        SWAP.with(|swap| swap.set((spent, received)));
+        ledger.process(&metas, &execute_data(proposal, vec![route]), now)
+    }
+
+    // SetPaused signed by the Fund's creator
+    fn set_paused(ledger: &mut TestLedger, fund_name: &str, paused: bool, nonce: u64, now: i64) -> ProgramResult {
+        let (fund_pda, _) = derive_fund_pda(&ledger.program_id, fund_name.as_bytes());
+        let creator = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().creator;
+        let mut data = vec![14, paused as u8];
+        data.extend_from_slice(&nonce.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&[(creator, true, false), (fund_pda, false, true)], &data, now)
+    }
+
+    #[test]
+    fn init_user_account_then_deposit_sol() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "alpha";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+
+        let member = Pubkey::new_unique();
+        ledger.fund_wallet(member, 10_000_000_000);
+        let (user_pda, _) = derive_user_pda(&program_id, &member);
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &member);
+
+        // InitUserAccount creates the user's accounts, the Fund-specific one not yet active
+        let mut data = vec![7, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&[
+            (member, true, true),
+            (user_pda, false, true),
+            (system_program::id(), false, false),
+            (fund_pda, false, false),
+            (user_specific_pda, false, true),
+        ], &data, 1_000).unwrap();
+
+        let user_data = UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap();
+        assert!(user_data.is_initialized);
+        assert!(!user_data.is_active);
+        assert_eq!(user_data.fund, fund_pda);
+        assert_eq!(user_data.pubkey, member);
+        assert_eq!(user_data.join_time, 1_000);
+        assert_eq!(UserAccount::try_from_slice(ledger.data(&user_pda)).unwrap().user, member);
+
+        // First deposit joins the public Fund and buys governance tokens 1:1
+        let member_wsol = Pubkey::new_unique();
+        ledger.set_account(member_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), member, 0));
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        ledger.set_account(member_governance, spl_token::id(), token_account(governance_mint, member, 0));
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+
+        let amount = 2_000_000_000_u64;
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, amount, 2_000).unwrap();
+
+        let user_data = UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap();
+        assert!(user_data.is_active);
+        assert_eq!(user_data.join_time, 2_000);
+        assert_eq!(user_data.deposit, amount);
+        assert_eq!(user_data.governance_token_balance, amount);
+        assert_eq!(user_data.last_deposit_time, 2_000);
+
+        let fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        assert_eq!(fund_data.members, vec![member]);
+        assert_eq!(fund_data.total_deposit, amount);
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, amount);
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, amount);
+    }
+
+    #[test]
+    fn deposit_requires_user_specific_account() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "beta";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+
+        let member = Pubkey::new_unique();
+        ledger.fund_wallet(member, 10_000_000_000);
+        let member_wsol = Pubkey::new_unique();
+        ledger.set_account(member_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), member, 0));
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        ledger.set_account(member_governance, spl_token::id(), token_account(governance_mint, member, 0));
+
+        let result = deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000, 2_000);
+
+        assert_eq!(result, Err(FundError::InvalidAccountData.into()));
+    }
+
+    #[test]
+    fn full_public_fund_turns_depositors_away() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "crowded";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let mut fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        fund_data.members = (0..MAX_FUND_MEMBERS).map(|_| Pubkey::new_unique()).collect();
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let result = deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 2_000);
+        assert_eq!(result, Err(FundError::FundFull.into()));
+    }
+
+    #[test]
+    fn member_who_joined_by_depositing_can_leave() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "revolving";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 2_000).unwrap();
+        let (user_pda, _) = derive_user_pda(&program_id, &member);
+        assert!(UserAccount::try_from_slice(ledger.data(&user_pda)).unwrap().funds.is_empty());
+
+        let mut data = vec![10, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        let metas = [
+            (member, true, true),
+            (derive_user_specific_pda(&program_id, &fund_pda, &member).0, false, true),
+            (fund_pda, false, true),
+            (user_pda, false, true),
+        ];
+
+        // Stake has to be withdrawn before the account recording it is closed
+        assert_eq!(ledger.process(&metas, &data, 3_000), Err(FundError::StakeNotWithdrawn.into()));
+
+        withdraw_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 3_000).unwrap();
+        ledger.process(&metas, &data, 3_000).unwrap();
+        assert!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().members.is_empty());
+    }
+
+    #[test]
+    fn deposit_rejects_look_alike_accounts() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "lookalike";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+
+        // Depositor's own WSOL account standing in for the vault's
+        let mut metas = deposit_sol_accounts(&program_id, fund_name, member, member_wsol);
+        metas[3].0 = member_wsol;
+        assert_eq!(ledger.process(&metas, &deposit_data(1_000_000, fund_name), 2_000), Err(FundError::InvalidTokenAccount.into()));
+
+        // Fund's data copied into an account the system program owns
+        let fund_data = ledger.data(&fund_pda).to_vec();
+        ledger.set_account(fund_pda, system_program::id(), fund_data);
+        let result = deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000, 2_000);
+        assert_eq!(result, Err(FundError::InvalidAccountData.into()));
+    }
+
+    #[test]
+    fn deposits_that_buy_no_shares_are_rejected() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "inflation";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+        let (attacker, attacker_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (victim, victim_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+
+        // A single-lamport share can't bootstrap the supply
+        let result = deposit_sol(&mut ledger, fund_name, attacker, attacker_wsol, 1, 2_000);
+        assert_eq!(result, Err(FundError::DepositTooSmall.into()));
+        deposit_sol(&mut ledger, fund_name, attacker, attacker_wsol, MIN_FIRST_DEPOSIT, 2_000).unwrap();
+
+        // Donation pushes the price of a share past what the victim's deposit can buy
+        ledger.set_account(vault_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), vault_pda, 1_000 * MIN_FIRST_DEPOSIT));
+        let result = deposit_sol(&mut ledger, fund_name, victim, victim_wsol, 999, 3_000);
+        assert_eq!(result, Err(FundError::DepositTooSmall.into()));
+        assert_eq!(TokenAccount::unpack(ledger.data(&victim_wsol)).unwrap().amount, 0);
+
+        // A deposit worth at least a share still goes through
+        deposit_sol(&mut ledger, fund_name, victim, victim_wsol, 1_000, 3_000).unwrap();
+    }
+
+    #[test]
+    fn deposit_splits_off_reserve_and_withdraw_draws_on_it_first() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "gamma";
+        let fund_pda = public_fund(&mut ledger, fund_name, FundConfig { reserve_bps: 2500, ..test_config() });
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let (reserve_pda, _) = derive_reserve_pda(&program_id, &fund_pda);
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+
+        // A quarter of the deposit lands in the reserve account, created on the way
+        let amount = 2_000_000_000_u64;
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, amount, 2_000).unwrap();
+
+        let reserve = TokenAccount::unpack(ledger.data(&reserve_pda)).unwrap();
+        assert_eq!(reserve.owner, vault_pda);
+        assert_eq!(reserve.mint, spl_token::native_mint::id());
+        assert_eq!(reserve.amount, 500_000_000);
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, 1_500_000_000);
+        // Governance tokens are minted against the whole deposit
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, amount);
+
+        // Withdrawal empties the reserve before touching the vault's WSOL
+        withdraw_sol(&mut ledger, fund_name, member, member_wsol, 800_000_000, 3_000).unwrap();
+
+        assert_eq!(TokenAccount::unpack(ledger.data(&reserve_pda)).unwrap().amount, 0);
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, 1_200_000_000);
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_wsol)).unwrap().amount, 800_000_000);
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, 1_200_000_000);
+    }
+
+    // CloseFund's accounts, every current member's User-specific PDA and WSOL account included
+    fn close_fund_accounts(ledger: &TestLedger, fund_name: &str, member_wsols: &[Pubkey]) -> Vec<(Pubkey, bool, bool)> {
+        let program_id = ledger.program_id;
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+
+        let mut metas = vec![
+            (fund_data.creator, true, true),
+            (fund_pda, false, true),
+            (vault_pda, false, true),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+            (spl_token::id(), false, false),
+            (fund_data.governance_mint, false, false),
+            (derive_reserve_pda(&program_id, &fund_pda).0, false, true),
+        ];
+        for accepted in fund_data.accepted_mints.iter().filter(|accepted| accepted.mint != spl_token::native_mint::id()) {
+            metas.push((spl_associated_token_account::get_associated_token_address(&vault_pda, &accepted.mint), false, false));
+        }
+        for (member, member_wsol) in fund_data.members.iter().zip(member_wsols) {
+            metas.push((derive_user_specific_pda(&program_id, &fund_pda, member).0, false, true));
+            metas.push((*member_wsol, false, true));
+        }
+        metas
+    }
+
+    #[test]
+    fn close_fund_splits_everything_but_rent_between_holders() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "closing";
+        let fund_pda = public_fund(&mut ledger, fund_name, FundConfig { reserve_bps: 5000, ..test_config() });
+        let (reserve_pda, _) = derive_reserve_pda(&program_id, &fund_pda);
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (alice, alice_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (bob, bob_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, alice, alice_wsol, 3_000_000_000, 2_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, bob, bob_wsol, 1_000_000_000, 2_000).unwrap();
+
+        // Bob is voted out but keeps his stake, which the close can't pay out to him
+        let mut fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        fund_data.members = vec![alice];
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+        let mut data = vec![19, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        let metas = close_fund_accounts(&ledger, fund_name, &[alice_wsol]);
+        assert_eq!(ledger.process(&metas, &data, 3_000), Err(FundError::FormerMemberBalances.into()));
+
+        withdraw_sol(&mut ledger, fund_name, bob, bob_wsol, 1_000_000_000, 3_000).unwrap();
+        assert_eq!(TokenAccount::unpack(ledger.data(&reserve_pda)).unwrap().amount, 1_000_000_000);
+
+        // Tokens bought with the vault's SOL can't be split as WSOL
+        let token_mint = Pubkey::new_unique();
+        fund_data.accepted_mints.push(AcceptedMint { mint: token_mint, price_feed: Pubkey::new_unique() });
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+        let vault_token = spl_associated_token_account::get_associated_token_address(&vault_pda, &token_mint);
+        ledger.set_account(vault_token, spl_token::id(), token_account(token_mint, vault_pda, 5));
+        let metas = close_fund_accounts(&ledger, fund_name, &[alice_wsol]);
+        assert_eq!(ledger.process(&metas, &data, 3_000), Err(FundError::TokenAccountNotEmpty.into()));
+        ledger.set_account(vault_token, spl_token::id(), token_account(token_mint, vault_pda, 0));
+
+        // Alice gets the vault's and the reserve's WSOL and the SOL sent straight to the vault, the
+        // creator gets back nothing but rent
+        ledger.accounts.get_mut(&vault_pda).unwrap().lamports += 500_000_000;
+        let creator = fund_data.creator;
+        let rent = Rent::default();
+        let expected_rent = rent.minimum_balance(ledger.data(&fund_pda).len()) +
+            rent.minimum_balance(VaultAccount::LEN) +
+            2 * rent.minimum_balance(TokenAccount::LEN);
+        ledger.process(&metas, &data, 3_000).unwrap();
+
+        assert_eq!(TokenAccount::unpack(ledger.data(&alice_wsol)).unwrap().amount, 3_500_000_000);
+        assert_eq!(TokenAccount::unpack(ledger.data(&bob_wsol)).unwrap().amount, 1_000_000_000);
+        assert_eq!(ledger.accounts[&creator].lamports, expected_rent);
+        assert_eq!(ledger.accounts[&reserve_pda].lamports, 0);
+        let (alice_user_specific, _) = derive_user_specific_pda(&program_id, &fund_pda, &alice);
+        assert_eq!(UserSpecificAccount::try_from_slice(ledger.data(&alice_user_specific)).unwrap().governance_token_balance, 0);
+    }
+
+    #[test]
+    fn migrate_fund_from_version_1() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "legacy";
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let creator = Pubkey::new_unique();
+        let member = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        ledger.fund_wallet(payer, 1_000_000_000);
+
+        // Fund, Vault and User-specific accounts as the first release wrote them
+        let old_fund = FundAccountV1 {
+            name: [7; 32],
+            creator,
+            members: 2,
+            total_deposit: 3_000,
+            governance_mint: Pubkey::new_unique(),
+            vault: vault_pda,
+            is_initialized: true,
+            created_at: 100,
+            is_private: 1,
+        };
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&old_fund).unwrap());
+        assert_eq!(ledger.data(&fund_pda).len(), 154);
+        ledger.set_account(vault_pda, program_id, borsh::to_vec(&VaultAccountV1 { fund: fund_pda, last_deposit_time: 200 }).unwrap());
+        let mut metas = vec![
+            (payer, true, true),
+            (fund_pda, false, true),
+            (system_program::id(), false, false),
+            (vault_pda, false, true),
+        ];
+        for (wallet, deposit) in [(creator, 1_000), (member, 2_000)] {
+            let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &wallet);
+            let old_user = UserSpecificAccountV1 {
+                pubkey: wallet,
+                fund: fund_pda,
+                deposit,
+                governance_token_balance: deposit,
+                is_active: true,
+                num_proposals: 1,
+                join_time: 150,
+            };
+            ledger.set_account(user_specific_pda, program_id, borsh::to_vec(&old_user).unwrap());
+            metas.push((wallet, false, false));
+            metas.push((user_specific_pda, false, true));
+        }
+
+        let mut data = vec![36, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+
+        // Member list must cover every member the version 1 Fund counted
+        assert_eq!(ledger.process(&metas[..6], &data, 1_000), Err(FundError::InvalidMemberCount.into()));
+
+        ledger.process(&metas, &data, 1_000).unwrap();
+
+        let fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        assert_eq!(fund_data.version, FUND_ACCOUNT_VERSION);
+        assert_eq!(fund_data.name, [7; 32]);
+        assert_eq!(fund_data.creator, creator);
+        assert_eq!(fund_data.members, vec![creator, member]);
+        assert_eq!(fund_data.total_deposit, 3_000);
+        assert_eq!(fund_data.created_at, 100);
+        assert_eq!(fund_data.is_private, 1);
+        assert!(fund_data.accepted_mints == vec![AcceptedMint { mint: spl_token::native_mint::id(), price_feed: SOL_USD_PRICE_FEED }]);
+        validate_fund_config(&fund_data.config).unwrap();
+
+        let vault_data = VaultAccount::try_from_slice(ledger.data(&vault_pda)).unwrap();
+        assert_eq!(vault_data.version, VAULT_ACCOUNT_VERSION);
+        assert_eq!(vault_data.last_deposit_time, 200);
+
+        for (wallet, roles) in [(creator, ROLE_ALL), (member, ROLE_PROPOSER)] {
+            let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &wallet);
+            let user_data = UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap();
+            assert_eq!(user_data.version, USER_SPECIFIC_ACCOUNT_VERSION);
+            assert_eq!(user_data.pubkey, wallet);
+            assert!(user_data.is_active && user_data.is_initialized);
+            assert_eq!(user_data.roles, roles);
+            assert_eq!(user_data.join_time, 150);
+        }
+
+        // Second run finds the Fund already current
+        assert_eq!(ledger.process(&metas, &data, 2_000), Err(FundError::AlreadyMigrated.into()));
+    }
+
+    #[test]
+    fn top_ups_after_a_proposal_keep_the_earlier_stake() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "snapshot";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (newcomer, newcomer_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+
+        let mut proposal_data = InvestmentProposalAccount::try_from_slice(&vec![0; InvestmentProposalAccount::space(0)]).unwrap();
+        proposal_data.created_at = 3_000;
+        let mut fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        fund_data.last_proposal_at = 3_000;
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+
+        // Two top-ups once the proposal is open, the second can't fold the first into the stake
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1, 4_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 5_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, newcomer, newcomer_wsol, 1_000_000_000, 5_000).unwrap();
+        let user_data = |ledger: &TestLedger, wallet: &Pubkey| {
+            let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, wallet);
+            UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap()
+        };
+        let config = test_config();
+        assert_eq!(tally_vote(&mut proposal_data, &user_data(&ledger, &member), &config, 6_000, 1).unwrap(), 2_000_000_000);
+
+        // Joined after the proposal was created, no say in it
+        assert_eq!(tally_vote(&mut proposal_data, &user_data(&ledger, &newcomer), &config, 6_000, 0).unwrap(), 0);
+
+        // Withdrawing below the earlier stake lowers it
+        withdraw_sol(&mut ledger, fund_name, member, member_wsol, 2_500_000_000, 7_000).unwrap();
+        assert_eq!(tally_vote(&mut proposal_data, &user_data(&ledger, &member), &config, 7_000, 1).unwrap(), 500_000_001);
+        assert_eq!(proposal_data.votes_yes, 2_500_000_001);
+    }
+
+    #[test]
+    fn quadratic_voting_dampens_a_large_holder() {
+        let mut proposal_data = InvestmentProposalAccount::try_from_slice(&vec![0; InvestmentProposalAccount::space(0)]).unwrap();
+        proposal_data.created_at = 1_000;
+        let mut user_data = UserSpecificAccount::try_from_slice(&[0; UserSpecificAccount::LEN]).unwrap();
+        user_data.governance_token_balance = 10_000;
+
+        let linear = FundConfig { voting_mode: VOTING_MODE_TOKEN_WEIGHTED, ..test_config() };
+        assert_eq!(tally_vote(&mut proposal_data, &user_data, &linear, 2_000, 1).unwrap(), 10_000);
+
+        let quadratic = FundConfig { voting_mode: VOTING_MODE_QUADRATIC, ..test_config() };
+        assert_eq!(tally_vote(&mut proposal_data, &user_data, &quadratic, 2_000, 0).unwrap(), 100);
+
+        assert_eq!(proposal_data.votes_yes, 10_000);
+        assert_eq!(proposal_data.votes_no, 100);
+    }
+
+    #[test]
+    fn refreshed_vault_value_prices_deposits_while_fresh() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "delta";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+        let creator = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().creator;
+
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+
+        // RefreshVaultValue caches the vault's WSOL, the only thing a WSOL-only Fund holds
+        let mut data = vec![32, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        ledger.process(&[
+            (creator, true, false),
+            (fund_pda, false, false),
+            (vault_pda, false, true),
+            (vault_wsol, false, false),
+            (derive_reserve_pda(&program_id, &fund_pda).0, false, false),
+        ], &data, 3_000).unwrap();
+        let vault_data = VaultAccount::try_from_slice(ledger.data(&vault_pda)).unwrap();
+        assert_eq!(vault_data.cached_value, 2_000_000_000);
+        assert_eq!(vault_data.last_valuation_time, 3_000);
+
+        // WSOL landing in the vault unseen by the cache, as a swap's proceeds would
+        ledger.set_account(vault_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), vault_pda, 4_000_000_000));
+
+        // While fresh the cache prices the deposit, at par, and moves with it
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 3_000 + VAULT_VALUE_MAX_AGE).unwrap();
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, 3_000_000_000);
+        assert_eq!(VaultAccount::try_from_slice(ledger.data(&vault_pda)).unwrap().cached_value, 3_000_000_000);
+
+        // Once stale, the vault's 5 SOL of WSOL back 3 SOL worth of tokens
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 3_001 + VAULT_VALUE_MAX_AGE).unwrap();
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, 3_600_000_000);
+    }
+
+    #[test]
+    fn change_vote_moves_the_stake_and_needs_an_active_member() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "turncoat";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (alice, alice_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (bob, bob_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, alice, alice_wsol, 2_000_000_000, 2_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, bob, bob_wsol, 1_000_000_000, 2_000).unwrap();
+
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_membership(&mut ledger, fund_name, alice, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, alice, proposal, 1, 4_000).unwrap();
+        cast_vote(&mut ledger, fund_name, bob, proposal, 1, 4_000).unwrap();
+
+        let (bob_user_specific, _) = derive_user_specific_pda(&program_id, &fund_pda, &bob);
+        let (bob_vote, _) = Pubkey::find_program_address(&[b"vote", proposal.as_ref(), bob.as_ref()], &program_id);
+        let metas = [
+            (bob, true, false),
+            (bob_vote, false, true),
+            (proposal, false, true),
+            (fund_pda, false, false),
+            (bob_user_specific, false, false),
+        ];
+        let change_vote_data = |vote: u8| {
+            let mut data = vec![18, vote, fund_name.len() as u8];
+            data.extend_from_slice(fund_name.as_bytes());
+            data
+        };
+
+        // Bob's whole stake moves from yes to no
+        ledger.process(&metas, &change_vote_data(0), 5_000).unwrap();
+        let proposal_data = InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap();
+        assert_eq!(proposal_data.votes_yes, 2_000_000_000);
+        assert_eq!(proposal_data.votes_no, 1_000_000_000);
+        assert_eq!(VoteAccount::try_from_slice(ledger.data(&bob_vote)).unwrap().vote, 0);
+
+        // Once removed from the Fund, Bob's vote stays where it is
+        let mut bob_data = UserSpecificAccount::try_from_slice(ledger.data(&bob_user_specific)).unwrap();
+        bob_data.is_active = false;
+        ledger.set_account(bob_user_specific, program_id, borsh::to_vec(&bob_data).unwrap());
+        assert_eq!(ledger.process(&metas, &change_vote_data(1), 6_000), Err(FundError::NotAFundMember.into()));
+        assert_eq!(InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().votes_no, 1_000_000_000);
+    }
+
+    #[test]
+    fn paused_fund_turns_away_deposits_proposals_and_votes() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "breaker";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+
+        set_paused(&mut ledger, fund_name, true, 0, 4_000).unwrap();
+        assert!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().is_paused);
+        assert_eq!(deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 4_000), Err(FundError::FundPaused.into()));
+        assert_eq!(
+            propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 4_000),
+            Err(FundError::FundPaused.into())
+        );
+        assert_eq!(cast_vote(&mut ledger, fund_name, member, proposal, 1, 4_000), Err(FundError::FundPaused.into()));
+
+        // Lifting the pause lets deposits through again
+        set_paused(&mut ledger, fund_name, false, 1, 5_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 5_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, proposal, 1, 5_000).unwrap();
+    }
+
+    #[test]
+    fn replayed_set_paused_is_stale() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "replay";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+
+        set_paused(&mut ledger, fund_name, true, 0, 1_000).unwrap();
+        set_paused(&mut ledger, fund_name, false, 1, 2_000).unwrap();
+
+        // Same signed pause sent again can't trip the breaker a second time
+        assert_eq!(set_paused(&mut ledger, fund_name, true, 0, 3_000), Err(FundError::StaleNonce.into()));
+        let fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        assert!(!fund_data.is_paused);
+        assert_eq!(fund_data.admin_nonce, 2);
+    }
+
+    #[test]
+    fn second_vote_on_a_proposal_is_rejected() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "once";
+        public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+
+        cast_vote(&mut ledger, fund_name, member, proposal, 1, 4_000).unwrap();
+        assert_eq!(cast_vote(&mut ledger, fund_name, member, proposal, 0, 5_000), Err(FundError::AlreadyVoted.into()));
+
+        // Fresh account in place of the vote PDA doesn't get around it
+        let mut metas = vote_accounts(&program_id, fund_name, member, proposal);
+        metas[1].0 = Pubkey::new_unique();
+        let mut data = vec![3, 1, fund_name.len() as u8];
+        data.extend_from_slice(fund_name.as_bytes());
+        assert_eq!(ledger.process(&metas, &data, 5_000), Err(FundError::InvalidAccountData.into()));
+        assert_eq!(InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().votes_yes, 2_000_000_000);
+    }
+
+    #[test]
+    fn only_members_can_propose() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "members";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+
+        let proposal = propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+        let proposal_data = InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap();
+        assert_eq!(proposal_data.proposer, member);
+        assert_eq!(proposal_data.fund, fund_pda);
+
+        // Stranger has a User-specific PDA for the Fund but never joined it
+        let (stranger, _) = new_depositor(&mut ledger, fund_name, 1_000);
+        assert_eq!(
+            propose_membership(&mut ledger, fund_name, stranger, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000),
+            Err(FundError::NotAFundMember.into())
+        );
+    }
+
+    #[test]
+    fn withdrawals_wait_out_the_lockup() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "locked";
+        public_fund(&mut ledger, fund_name, FundConfig { lockup_seconds: 7 * 24 * 60 * 60, ..test_config() });
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+
+        let unlock = 2_000 + 7 * 24 * 60 * 60;
+        assert_eq!(withdraw_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, unlock - 1), Err(FundError::LockupActive.into()));
+        withdraw_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, unlock).unwrap();
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_wsol)).unwrap().amount, 1_000_000_000);
+
+        // Topping up restarts the clock
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 500_000_000, unlock + 10).unwrap();
+        assert_eq!(withdraw_sol(&mut ledger, fund_name, member, member_wsol, 1, unlock + 11), Err(FundError::LockupActive.into()));
+    }
+
+    #[test]
+    fn swap_below_min_out_rolls_back_the_execution() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "slippage";
+        let (member, to_mint) = investing_fund(&mut ledger, fund_name);
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+        let vault_to_ata = spl_associated_token_account::get_associated_token_address(&vault_pda, &to_mint);
+
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_swap(&mut ledger, fund_name, member, to_mint, 1_000_000_000, 500, deadline, 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, proposal, 1, 4_000).unwrap();
+
+        // Route paying one unit short of min_out fails the whole execution
+        assert_eq!(
+            execute_swap(&mut ledger, fund_name, proposal, to_mint, 1_000_000_000, 499, &[], deadline + 1),
+            Err(FundError::SlippageExceeded.into())
+        );
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, 2_000_000_000);
+        assert!(!InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().executed);
+
+        execute_swap(&mut ledger, fund_name, proposal, to_mint, 1_000_000_000, 500, &[], deadline + 1).unwrap();
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, 1_000_000_000);
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_to_ata)).unwrap().amount, 500);
+        assert!(InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().executed);
+    }
+
+    #[test]
+    fn jupiter_route_cannot_overspend_or_reach_other_vault_accounts() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "route";
+        let (member, to_mint) = investing_fund(&mut ledger, fund_name);
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (reserve_pda, _) = derive_reserve_pda(&program_id, &fund_pda);
+        ledger.set_account(reserve_pda, spl_token::id(), token_account(spl_token::native_mint::id(), vault_pda, 100_000_000));
+
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_swap(&mut ledger, fund_name, member, to_mint, 1_000_000_000, 500, deadline, 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, proposal, 1, 4_000).unwrap();
+
+        // Route taking a lamport more than the leg's amount
+        assert_eq!(
+            execute_swap(&mut ledger, fund_name, proposal, to_mint, 1_000_000_001, 500, &[], deadline + 1),
+            Err(FundError::SwapOverspent.into())
+        );
+
+        // Vault signs for the whole route, so the reserve may not ride along in it
+        assert_eq!(
+            execute_swap(&mut ledger, fund_name, proposal, to_mint, 1_000_000_000, 500, &[(reserve_pda, false, true)], deadline + 1),
+            Err(FundError::InvalidTokenAccount.into())
+        );
+
+        let pool = Pubkey::new_unique();
+        ledger.set_account(pool, spl_token::id(), token_account(to_mint, Pubkey::new_unique(), 1_000));
+        execute_swap(&mut ledger, fund_name, proposal, to_mint, 1_000_000_000, 500, &[(pool, false, true)], deadline + 1).unwrap();
+    }
+
+    #[test]
+    fn quorum_raised_by_a_passed_proposal_holds_later_proposals() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "quorum";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (alice, alice_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (bob, bob_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, alice, alice_wsol, 3_000_000_000, 2_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, bob, bob_wsol, 1_000_000_000, 2_000).unwrap();
+
+        let config_change = ConfigChange {
+            name: FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().name,
+            is_private: 0,
+            quorum_bps: 9000,
+            pass_threshold_bps: 5000,
+            dex_allowlist: 0,
+        };
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let mut data = vec![20];
+        data.extend(borsh::to_vec(&config_change).unwrap());
+        data.extend_from_slice(&deadline.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        let proposal = propose(&mut ledger, fund_name, alice, &data, &[], 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, alice, proposal, 1, 4_000).unwrap();
+        cast_vote(&mut ledger, fund_name, bob, proposal, 1, 4_000).unwrap();
+        let metas = execute_accounts(&mut ledger, fund_name, proposal);
+        ledger.process(&metas, &execute_data(proposal, vec![]), deadline + 1).unwrap();
+        assert_eq!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().config.quorum_bps, 9000);
+
+        // Alice's 75% would have cleared the old quorum, not the new one
+        let deadline = deadline + 2 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_membership(&mut ledger, fund_name, alice, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, deadline - DEFAULT_MIN_VOTING_PERIOD).unwrap();
+        cast_vote(&mut ledger, fund_name, alice, proposal, 1, deadline - 1).unwrap();
+        let metas = execute_accounts(&mut ledger, fund_name, proposal);
+        assert_eq!(ledger.process(&metas, &execute_data(proposal, vec![]), deadline + 1), Err(FundError::QuorumNotReached.into()));
+    }
+
+    #[test]
+    fn second_u64_max_deposit_fails_without_wrapping() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "whale";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (member, _) = new_depositor(&mut ledger, fund_name, 1_000);
+
+        // Token priced at par with SOL, the only way a deposit can be worth u64::MAX lamports
+        let token_mint = Pubkey::new_unique();
+        let price_feed = Pubkey::new_unique();
+        ledger.set_account(token_mint, spl_token::id(), mint(Pubkey::new_unique(), u64::MAX));
+        for feed in [SOL_USD_PRICE_FEED, price_feed] {
+            ledger.set_account(feed, PYTH_PROGRAM_ID, price_account(100_000_000, 0, -8, 2_000));
+        }
+        let mut fund_data = FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap();
+        fund_data.accepted_mints.push(AcceptedMint { mint: token_mint, price_feed });
+        ledger.set_account(fund_pda, program_id, borsh::to_vec(&fund_data).unwrap());
+        let mut vault_data = VaultAccount::try_from_slice(ledger.data(&vault_pda)).unwrap();
+        vault_data.last_valuation_time = 2_000;
+        ledger.set_account(vault_pda, program_id, borsh::to_vec(&vault_data).unwrap());
+
+        let member_tokens = Pubkey::new_unique();
+        let vault_tokens = spl_associated_token_account::get_associated_token_address(&vault_pda, &token_mint);
+        ledger.set_account(vault_tokens, spl_token::id(), token_account(token_mint, vault_pda, 0));
+        let mut metas = deposit_sol_accounts(&program_id, fund_name, member, member_tokens);
+        metas[3].0 = vault_tokens;
+        metas[4].0 = token_mint;
+        metas.pop();
+        metas.extend([(SOL_USD_PRICE_FEED, false, false), (price_feed, false, false)]);
+
+        ledger.set_account(member_tokens, spl_token::id(), token_account(token_mint, member, u64::MAX));
+        ledger.process(&metas, &deposit_data(u64::MAX, fund_name), 2_000).unwrap();
+        assert_eq!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().total_deposit, u64::MAX);
+
+        // Second one can't wrap the vault's balance and errors out, leaving everything as it was
+        ledger.set_account(member_tokens, spl_token::id(), token_account(token_mint, member, u64::MAX));
+        assert_eq!(
+            ledger.process(&metas, &deposit_data(u64::MAX, fund_name), 2_000),
+            Err(spl_token::error::TokenError::Overflow.into())
+        );
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &member);
+        let user_data = UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap();
+        assert_eq!(user_data.deposit, u64::MAX);
+        assert_eq!(user_data.governance_token_balance, u64::MAX);
+        assert_eq!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().total_deposit, u64::MAX);
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_tokens)).unwrap().amount, u64::MAX);
+    }
+
+    #[test]
+    fn deposit_fee_pays_the_collector_its_exact_cut() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "fees";
+        let collector = Pubkey::new_unique();
+        let fund_pda = public_fund(&mut ledger, fund_name, FundConfig { deposit_fee_bps: 250, fee_collector: collector, ..test_config() });
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let collector_wsol = Pubkey::new_unique();
+        ledger.set_account(collector_wsol, spl_token::id(), token_account(spl_token::native_mint::id(), collector, 0));
+
+        // Fee account goes right before the reserve
+        let mut metas = deposit_sol_accounts(&program_id, fund_name, member, member_wsol);
+        metas.insert(metas.len() - 1, (collector_wsol, false, true));
+        ledger.process(&metas, &deposit_data(2_000_000_001, fund_name), 2_000).unwrap();
+
+        // 2.5% of 2_000_000_001 rounded down, the depositor is credited with the rest
+        assert_eq!(TokenAccount::unpack(ledger.data(&collector_wsol)).unwrap().amount, 50_000_000);
+        let vault_wsol = spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id());
+        assert_eq!(TokenAccount::unpack(ledger.data(&vault_wsol)).unwrap().amount, 1_950_000_001);
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, 1_950_000_001);
+        assert_eq!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().total_deposit, 1_950_000_001);
+    }
+
+    #[test]
+    fn spoofed_token_program_is_rejected() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "spoof";
+        public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let spoofed_token_program = Pubkey::new_unique();
+        ledger.accounts.insert(spoofed_token_program, TestAccount { owner: Pubkey::default(), lamports: 1, data: vec![], executable: true });
+
+        let mut metas = deposit_sol_accounts(&program_id, fund_name, member, member_wsol);
+        metas[5].0 = spoofed_token_program;
+        assert_eq!(ledger.process(&metas, &deposit_data(2_000_000_000, fund_name), 2_000), Err(FundError::InvalidAccountData.into()));
+
+        // Same for the withdrawal that would hand the vault's authority to it
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let mut data = vec![12];
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        data.push(fund_name.len() as u8);
+        data.extend_from_slice(fund_name.as_bytes());
+        assert_eq!(ledger.process(&[
+            (member, true, true),
+            (member_wsol, false, true),
+            (vault_pda, false, true),
+            (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+            (fund_pda, false, true),
+            (derive_user_specific_pda(&program_id, &fund_pda, &member).0, false, true),
+            (governance_mint, false, true),
+            (spl_associated_token_account::get_associated_token_address(&member, &governance_mint), false, true),
+            (spoofed_token_program, false, false),
+            (derive_reserve_pda(&program_id, &fund_pda).0, false, true),
+        ], &data, 3_000), Err(FundError::InvalidAccountData.into()));
+    }
+
+    #[test]
+    fn withdrawal_proposals_cannot_pay_the_proposer() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "selfdeal";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let (alice, alice_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        let (bob, bob_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, alice, alice_wsol, 2_000_000_000, 2_000).unwrap();
+        deposit_sol(&mut ledger, fund_name, bob, bob_wsol, 1_000_000_000, 2_000).unwrap();
+        let (alice_user_specific, _) = derive_user_specific_pda(&program_id, &fund_pda, &alice);
+        let mut alice_data = UserSpecificAccount::try_from_slice(ledger.data(&alice_user_specific)).unwrap();
+        alice_data.roles = ROLE_ALL;
+        ledger.set_account(alice_user_specific, program_id, borsh::to_vec(&alice_data).unwrap());
+
+        // Bob's account with Alice as its delegate, she could spend whatever lands in it
+        let delegated_wsol = Pubkey::new_unique();
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount {
+            delegate: COption::Some(alice),
+            delegated_amount: u64::MAX,
+            ..TokenAccount::unpack(&token_account(spl_token::native_mint::id(), bob, 0)).unwrap()
+        }.pack_into_slice(&mut data);
+        ledger.set_account(delegated_wsol, spl_token::id(), data);
+
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        for (recipient, recipient_wsol) in [(alice, alice_wsol), (bob, delegated_wsol)] {
+            let mut data = vec![17];
+            data.extend_from_slice(recipient.as_ref());
+            data.extend_from_slice(&500_000_000u64.to_le_bytes());
+            data.extend_from_slice(&deadline.to_le_bytes());
+            data.push(fund_name.len() as u8);
+            data.extend_from_slice(fund_name.as_bytes());
+            let proposal = propose(&mut ledger, fund_name, alice, &data, &[], 3_000).unwrap();
+            cast_vote(&mut ledger, fund_name, alice, proposal, 1, 4_000).unwrap();
+            cast_vote(&mut ledger, fund_name, bob, proposal, 1, 4_000).unwrap();
+
+            let mut metas = execute_accounts(&mut ledger, fund_name, proposal);
+            metas.extend([
+                (spl_associated_token_account::get_associated_token_address(&vault_pda, &spl_token::native_mint::id()), false, true),
+                (recipient_wsol, false, true),
+                (derive_reserve_pda(&program_id, &fund_pda).0, false, true),
+            ]);
+            assert_eq!(ledger.process(&metas, &execute_data(proposal, vec![]), deadline + 1), Err(FundError::InvalidProposalTarget.into()));
+        }
+    }
+
+    #[test]
+    fn open_proposals_per_member_are_capped() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "spam";
+        let fund_pda = public_fund(&mut ledger, fund_name, FundConfig { max_open_proposals_per_member: 2, ..test_config() });
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+
+        for _ in 0..2 {
+            propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+        }
+        assert_eq!(
+            propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000),
+            Err(FundError::TooManyOpenProposals.into())
+        );
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &member);
+        assert_eq!(UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap().open_proposals, 2);
+    }
+
+    #[test]
+    fn proposal_count_is_persisted() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "counter";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+
+        propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+        propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &member);
+        assert_eq!(UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap().num_proposals, 2);
+    }
+
+    #[test]
+    fn one_proposer_can_hold_concurrent_proposals() {
+        let mut ledger = TestLedger::new();
+        let fund_name = "concurrent";
+        public_fund(&mut ledger, fund_name, test_config());
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let first_candidate = Pubkey::new_unique();
+        let second_candidate = Pubkey::new_unique();
+
+        let first = propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, first_candidate, deadline, 3_000).unwrap();
+        let second = propose_membership(&mut ledger, fund_name, member, PROPOSAL_KIND_ADD_MEMBER, second_candidate, deadline, 3_000).unwrap();
+        assert_ne!(first, second);
+
+        // Each keeps its own target and takes votes of its own
+        cast_vote(&mut ledger, fund_name, member, first, 1, 4_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, second, 0, 4_000).unwrap();
+        let first_data = InvestmentProposalAccount::try_from_slice(ledger.data(&first)).unwrap();
+        let second_data = InvestmentProposalAccount::try_from_slice(ledger.data(&second)).unwrap();
+        assert_eq!((first_data.target_member, first_data.votes_yes, first_data.votes_no), (first_candidate, 2_000_000_000, 0));
+        assert_eq!((second_data.target_member, second_data.votes_yes, second_data.votes_no), (second_candidate, 0, 2_000_000_000));
+    }
+
+    #[test]
+    fn member_votes_after_depositing_from_a_non_ata_account() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "voter";
+        public_fund(&mut ledger, fund_name, test_config());
+        let (proposer, proposer_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, proposer, proposer_wsol, 2_000_000_000, 2_000).unwrap();
+
+        // new_depositor's WSOL account is a plain keypair account, not the member's ATA
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        assert_ne!(member_wsol, spl_associated_token_account::get_associated_token_address(&member, &spl_token::native_mint::id()));
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 1_000_000_000, 2_000).unwrap();
+
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let proposal = propose_membership(&mut ledger, fund_name, proposer, PROPOSAL_KIND_ADD_MEMBER, Pubkey::new_unique(), deadline, 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, proposal, 1, 4_000).unwrap();
+
+        let (vote_pda, _) = Pubkey::find_program_address(&[b"vote", proposal.as_ref(), member.as_ref()], &program_id);
+        let vote_data = VoteAccount::try_from_slice(ledger.data(&vote_pda)).unwrap();
+        assert_eq!((vote_data.voter, vote_data.vote, vote_data.voting_power), (member, 1, 1_000_000_000));
+        assert_eq!(InvestmentProposalAccount::try_from_slice(ledger.data(&proposal)).unwrap().votes_yes, 1_000_000_000);
+    }
+
+    #[test]
+    fn partial_withdrawal_burns_the_redeemed_share() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "burn";
+        let fund_pda = public_fund(&mut ledger, fund_name, test_config());
+        let (governance_mint, _) = derive_governance_mint_pda(&program_id, &fund_pda);
+        let (member, member_wsol) = new_depositor(&mut ledger, fund_name, 1_000);
+        deposit_sol(&mut ledger, fund_name, member, member_wsol, 2_000_000_000, 2_000).unwrap();
+
+        withdraw_sol(&mut ledger, fund_name, member, member_wsol, 500_000_000, 3_000).unwrap();
+
+        // Mint supply, the member's token account and both recorded counters all drop together
+        assert_eq!(Mint::unpack(ledger.data(&governance_mint)).unwrap().supply, 1_500_000_000);
+        let member_governance = spl_associated_token_account::get_associated_token_address(&member, &governance_mint);
+        assert_eq!(TokenAccount::unpack(ledger.data(&member_governance)).unwrap().amount, 1_500_000_000);
+        let (user_specific_pda, _) = derive_user_specific_pda(&program_id, &fund_pda, &member);
+        assert_eq!(UserSpecificAccount::try_from_slice(ledger.data(&user_specific_pda)).unwrap().governance_token_balance, 1_500_000_000);
+        assert_eq!(FundAccount::try_from_slice(ledger.data(&fund_pda)).unwrap().total_deposit, 1_500_000_000);
+    }
+
+    #[test]
+    fn swap_can_wrap_the_vault_down_to_rent_and_no_further() {
+        let mut ledger = TestLedger::new();
+        let program_id = ledger.program_id;
+        let fund_name = "drain";
+        let (member, to_mint) = investing_fund(&mut ledger, fund_name);
+        let (fund_pda, _) = derive_fund_pda(&program_id, fund_name.as_bytes());
+        let (vault_pda, _) = Pubkey::find_program_address(&[b"vault", fund_pda.as_ref()], &program_id);
+        let vault_rent = Rent::default().minimum_balance(VaultAccount::LEN);
+        assert_eq!(ledger.accounts[&vault_pda].lamports, vault_rent);
+
+        // Native SOL sent straight to the Vault PDA tops up a SOL leg past the vault's 2 SOL of WSOL
+        ledger.accounts.get_mut(&vault_pda).unwrap().lamports += 1_000_000;
+        let deadline = 3_000 + DEFAULT_MIN_VOTING_PERIOD;
+        let too_much = propose_swap(&mut ledger, fund_name, member, to_mint, 2_001_000_001, 500, deadline, 3_000).unwrap();
+        let exact = propose_swap(&mut ledger, fund_name, member, to_mint, 2_001_000_000, 500, deadline, 3_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, too_much, 1, 4_000).unwrap();
+        cast_vote(&mut ledger, fund_name, member, exact, 1, 4_000).unwrap();
+
+        assert_eq!(
+            execute_swap(&mut ledger, fund_name, too_much, to_mint, 2_001_000_001, 500, &[], deadline + 1),
+            Err(FundError::InsufficientVaultFunds.into())
+        );
+        execute_swap(&mut ledger, fund_name, exact, to_mint, 2_001_000_000, 500, &[], deadline + 1).unwrap();
+        assert_eq!(ledger.accounts[&vault_pda].lamports, vault_rent);
+    }
+}
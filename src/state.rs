@@ -1,59 +1,457 @@
-use solana_program::pubkey::Pubkey;
-use borsh::{BorshSerialize, BorshDeserialize};
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct FundAccount {
-    pub name: [u8; 32],
-    pub creator: Pubkey,
-    pub members: u64,
-    pub total_deposit: u64,
-    pub governance_mint: Pubkey,
-    pub vault: Pubkey,
-    pub is_initialized: bool,
-    pub created_at: i64,
-    pub is_private: u8,
-    // pub dex_program_ids: Vec<(u8, Pubkey)>,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct VaultAccount {
-    pub fund: Pubkey,
-    pub last_deposit_time: i64,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct UserAccount {
-    pub user: Pubkey,
-    pub funds: Vec<Pubkey>,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct UserSpecificAccount {
-    pub pubkey: Pubkey,
-    pub fund: Pubkey,
-    pub deposit: u64,
-    pub governance_token_balance: u64,
-    pub is_active: bool,
-    pub num_proposals: u8,
-    pub join_time: i64,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct InvestmentProposalAccount {
-    pub fund: Pubkey,
-    pub proposer: Pubkey,
-    pub from_assets: Vec<Pubkey>,
-    pub to_assets: Vec<Pubkey>,
-    pub amounts: Vec<u64>,
-    // pub dex_tags: Vec<u8>,
-    pub votes_yes: u64,
-    pub votes_no: u64,
-    pub deadline: i64,
-    pub executed: bool,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct VoteAccount {
-    pub voter: Pubkey,
-    pub vote: u8,
-}
+use solana_program::pubkey::Pubkey;
+use borsh::{BorshSerialize, BorshDeserialize};
+
+// Governance settings chosen at Fund creation
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct FundConfig {
+    pub quorum_bps: u16,
+    // Share of cast votes that must be yes, e.g. 6667 for two-thirds
+    pub pass_threshold_bps: u16,
+    pub governance_decimals: u8,
+    // Deposit caps in lamports, zero means no cap
+    pub max_total_deposit: u64,
+    pub max_member_deposit: u64,
+    // Minimum time deposits stay in the vault before they can be withdrawn
+    pub lockup_seconds: i64,
+    pub min_proposal_deposit: u64,
+    // Share of every deposit paid to fee_collector, in basis points
+    pub deposit_fee_bps: u16,
+    pub fee_collector: Pubkey,
+    // Lamports a proposer escrows with every proposal, forfeited to the vault's WSOL for spam
+    pub proposal_bond: u64,
+    // VOTING_MODE_* deciding how a member's governance tokens translate into voting power
+    pub voting_mode: u8,
+    // Tenure at which a time-weighted member reaches full voting power
+    pub time_weight_cap_seconds: i64,
+    // Bitmask of DEXes investment legs may route through, bit n allows the DEX tagged n
+    pub dex_allowlist: u32,
+    // Shortest voting window a proposal may set, on top of the program-wide MIN_PROPOSAL_WINDOW
+    pub min_voting_period: i64,
+    // A vote cast within anti_snipe_window of the deadline pushes it out by deadline_extension,
+    // zero disables extensions
+    pub anti_snipe_window: i64,
+    pub deadline_extension: i64,
+    // Lamports paid from the Vault PDA to whoever completes a proposal's execution
+    pub execution_reward: u64,
+    // Time a passed proposal waits after its deadline before it can be executed, giving members
+    // who voted against it a window to withdraw
+    pub execution_delay: i64,
+    // Share of every SOL deposit moved into the Fund's reserve account, a liquid reserve investment
+    // legs can't spend, in basis points
+    pub reserve_bps: u16,
+    // Most proposals a member can have open at once, zero means no limit
+    pub max_open_proposals_per_member: u8,
+    // Oldest Pyth aggregate, in seconds, the Fund's valuations and deposits accept
+    pub max_price_age: i64,
+}
+
+// Mint the Fund accepts and the Pyth price account it's valued with, fixed at Fund creation
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq)]
+pub struct AcceptedMint {
+    pub mint: Pubkey,
+    pub price_feed: Pubkey,
+}
+
+impl AcceptedMint {
+    pub const LEN: usize =
+        32 + // mint
+        32; // price_feed
+}
+
+// Layout version written as the first byte of every Fund account, MigrateFund brings older
+// accounts up to it
+pub const FUND_ACCOUNT_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FundAccount {
+    pub version: u8,
+    pub name: [u8; 32],
+    pub creator: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub total_deposit: u64,
+    pub governance_mint: Pubkey,
+    pub vault: Pubkey,
+    pub is_initialized: bool,
+    pub created_at: i64,
+    pub is_private: u8,
+    pub is_paused: bool,
+    // Set by the creator to stop new deposits, and with them new governance tokens
+    pub deposits_frozen: bool,
+    // Expected by the next creator-only instruction and bumped by it, so a replayed or stale admin
+    // transaction fails
+    pub admin_nonce: u64,
+    pub active_proposals: u32,
+    // Creation time of the Fund's latest proposal, 0 before its first one
+    pub last_proposal_at: i64,
+    pub config: FundConfig,
+    pub accepted_mints: Vec<AcceptedMint>,
+    // pub dex_program_ids: Vec<(u8, Pubkey)>,
+}
+
+// Version 1 Fund account, the shipped layout before the version byte, when members was only a
+// count. Only read by MigrateFund.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FundAccountV1 {
+    pub name: [u8; 32],
+    pub creator: Pubkey,
+    pub members: u64,
+    pub total_deposit: u64,
+    pub governance_mint: Pubkey,
+    pub vault: Pubkey,
+    pub is_initialized: bool,
+    pub created_at: i64,
+    pub is_private: u8,
+}
+
+impl FundConfig {
+    // quorum_bps, pass_threshold_bps, governance_decimals, max_total_deposit, max_member_deposit,
+    // lockup_seconds, min_proposal_deposit, deposit_fee_bps, fee_collector, proposal_bond,
+    // voting_mode, time_weight_cap_seconds, dex_allowlist, min_voting_period, anti_snipe_window,
+    // deadline_extension, execution_reward, execution_delay, reserve_bps, max_open_proposals_per_member,
+    // max_price_age
+    pub const LEN: usize = 2 + 2 + 1 + 8 + 8 + 8 + 8 + 2 + 32 + 8 + 1 + 8 + 4 + 8 + 8 + 8 + 8 + 8 + 2 + 1 + 8;
+}
+
+impl FundAccount {
+    // Borsh size of a Fund account, Vecs are stored as a 4-byte length followed by their items
+    pub const fn space(num_members: usize, num_accepted_mints: usize) -> usize {
+        1 + // version
+        32 + // name
+        32 + // creator
+        4 + 32 * num_members + // members
+        8 + // total_deposit
+        32 + // governance_mint
+        32 + // vault
+        1 + // is_initialized
+        8 + // created_at
+        1 + // is_private
+        1 + // is_paused
+        1 + // deposits_frozen
+        8 + // admin_nonce
+        4 + // active_proposals
+        8 + // last_proposal_at
+        FundConfig::LEN + // config
+        4 + AcceptedMint::LEN * num_accepted_mints // accepted_mints
+    }
+
+    pub fn accepted_mint(&self, mint: &Pubkey) -> Option<&AcceptedMint> {
+        self.accepted_mints.iter().find(|accepted| accepted.mint == *mint)
+    }
+
+    // Vault of a Fund accepting only WSOL holds nothing but WSOL, so its WSOL balance is its value
+    pub fn accepts_only_wsol(&self) -> bool {
+        self.accepted_mints.iter().all(|accepted| accepted.mint == spl_token::native_mint::id())
+    }
+}
+
+// Vault PDA is a program-owned record, not a token account. Tokens, WSOL included, are held in
+// the Vault PDA's associated token accounts, which it signs for.
+// Layout version written as the first byte of every Vault account
+pub const VAULT_ACCOUNT_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VaultAccount {
+    pub version: u8,
+    pub fund: Pubkey,
+    pub last_deposit_time: i64,
+    // Vault value in lamports as of `last_valuation_time`, written by RefreshVaultValue
+    pub cached_value: u64,
+    pub last_valuation_time: i64,
+}
+
+impl VaultAccount {
+    // version, fund, last_deposit_time, cached_value, last_valuation_time
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 8;
+}
+
+// Version 1 Vault account, the shipped layout before the version byte. Only read by MigrateFund.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VaultAccountV1 {
+    pub fund: Pubkey,
+    pub last_deposit_time: i64,
+}
+
+// Fund's index of proposals that haven't been closed yet, so clients can find them without
+// knowing every proposer's PDAs
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProposalRegistry {
+    pub fund: Pubkey,
+    pub proposals: Vec<Pubkey>,
+}
+
+impl ProposalRegistry {
+    // fund, then proposals as a 4-byte length followed by their keys
+    pub const fn space(num_proposals: usize) -> usize {
+        32 + 4 + 32 * num_proposals
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct UserAccount {
+    pub user: Pubkey,
+    pub funds: Vec<Pubkey>,
+}
+
+// Layout version written as the first byte of every User-specific account
+pub const USER_SPECIFIC_ACCOUNT_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct UserSpecificAccount {
+    pub version: u8,
+    pub pubkey: Pubkey,
+    pub fund: Pubkey,
+    pub deposit: u64,
+    pub governance_token_balance: u64,
+    pub is_active: bool,
+    pub num_proposals: u8,
+    pub join_time: i64,
+    pub last_deposit_time: i64,
+    // Member whose votes also carry this member's power, Pubkey::default() when not delegating
+    pub delegate: Pubkey,
+    // Set once the account has been written, a created-but-unwritten account reads as all zeroes
+    pub is_initialized: bool,
+    // ROLE_* bits deciding which proposal kinds the member may create
+    pub roles: u8,
+    // Member's proposals that haven't been closed yet, capped by max_open_proposals_per_member
+    pub open_proposals: u8,
+    // Stake the member votes with on proposals created before last_deposit_time, u64::MAX while
+    // no such proposal can still take votes
+    pub pre_deposit_balance: u64,
+}
+
+impl UserSpecificAccount {
+    pub const LEN: usize =
+        1 + // version
+        32 + // pubkey
+        32 + // fund
+        8 + // deposit
+        8 + // governance_token_balance
+        1 + // is_active
+        1 + // num_proposals
+        8 + // join_time
+        8 + // last_deposit_time
+        32 + // delegate
+        1 + // is_initialized
+        1 + // roles
+        1 + // open_proposals
+        8; // pre_deposit_balance
+}
+
+// Version 1 User-specific account, the shipped layout before the version byte. Only read by
+// MigrateFund.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct UserSpecificAccountV1 {
+    pub pubkey: Pubkey,
+    pub fund: Pubkey,
+    pub deposit: u64,
+    pub governance_token_balance: u64,
+    pub is_active: bool,
+    pub num_proposals: u8,
+    pub join_time: i64,
+}
+
+// Member roles stored in UserSpecificAccount::roles. Every member can propose, admins can also
+// propose withdrawals, member removals and role changes. The Fund's creator starts with all roles.
+pub const ROLE_PROPOSER: u8 = 1 << 0;
+pub const ROLE_ADMIN: u8 = 1 << 1;
+pub const ROLE_ALL: u8 = ROLE_PROPOSER | ROLE_ADMIN;
+
+// Proposal kinds stored in InvestmentProposalAccount::proposal_kind
+pub const PROPOSAL_KIND_INVESTMENT: u8 = 0;
+pub const PROPOSAL_KIND_ADD_MEMBER: u8 = 1;
+pub const PROPOSAL_KIND_REMOVE_MEMBER: u8 = 2;
+pub const PROPOSAL_KIND_WITHDRAWAL: u8 = 3;
+pub const PROPOSAL_KIND_CONFIG_CHANGE: u8 = 4;
+pub const PROPOSAL_KIND_SET_ROLES: u8 = 5;
+
+// Voting modes stored in FundConfig::voting_mode
+pub const VOTING_MODE_TOKEN_WEIGHTED: u8 = 0;
+pub const VOTING_MODE_TIME_WEIGHTED: u8 = 1;
+// Voting power is the square root of the member's governance tokens, damping large holders
+pub const VOTING_MODE_QUADRATIC: u8 = 2;
+
+// Fund settings a config-change proposal replaces once it passes
+#[derive(BorshSerialize, BorshDeserialize, Clone, Default)]
+pub struct ConfigChange {
+    pub name: [u8; 32],
+    pub is_private: u8,
+    pub quorum_bps: u16,
+    pub pass_threshold_bps: u16,
+    pub dex_allowlist: u32,
+}
+
+// Layout version written as the first byte of every proposal account
+pub const PROPOSAL_ACCOUNT_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct InvestmentProposalAccount {
+    pub version: u8,
+    pub fund: Pubkey,
+    pub proposer: Pubkey,
+    pub proposal_kind: u8,
+    pub target_member: Pubkey,
+    // Withdrawal proposals pay `lamports` of the vault's WSOL to `recipient`
+    pub recipient: Pubkey,
+    pub lamports: u64,
+    pub config_change: ConfigChange,
+    pub from_assets: Vec<Pubkey>,
+    pub to_assets: Vec<Pubkey>,
+    pub amounts: Vec<u64>,
+    // Minimum output accepted for each leg's swap
+    pub min_out: Vec<u64>,
+    pub dex_tags: Vec<u8>,
+    pub votes_yes: u64,
+    pub votes_no: u64,
+    pub deadline: i64,
+    pub executed: bool,
+    // Bit i is set once leg i of an investment proposal has been swapped
+    pub legs_executed: u8,
+    // Governance supply and time at creation; voting power is measured against this snapshot
+    pub snapshot_supply: u64,
+    pub created_at: i64,
+    // Lamports held in the proposal account on top of its rent
+    pub bond: u64,
+    // Withdrawn by the proposer before anyone voted, can no longer be voted on or executed
+    pub cancelled: bool,
+    pub is_initialized: bool,
+    // Set-roles proposals replace target_member's roles with these
+    pub roles: u8,
+}
+
+impl ConfigChange {
+    // name, is_private, quorum_bps, pass_threshold_bps, dex_allowlist
+    pub const LEN: usize = 32 + 1 + 2 + 2 + 4;
+}
+
+impl InvestmentProposalAccount {
+    // Borsh size of a proposal with `num_legs` asset legs, Vecs are stored as a 4-byte length
+    // followed by their items
+    pub const fn space(num_legs: usize) -> usize {
+        1 + // version
+        32 + // fund
+        32 + // proposer
+        1 + // proposal_kind
+        32 + // target_member
+        32 + // recipient
+        8 + // lamports
+        ConfigChange::LEN + // config_change
+        4 + 32 * num_legs + // from_assets
+        4 + 32 * num_legs + // to_assets
+        4 + 8 * num_legs + // amounts
+        4 + 8 * num_legs + // min_out
+        4 + num_legs + // dex_tags
+        8 + // votes_yes
+        8 + // votes_no
+        8 + // deadline
+        1 + // executed
+        1 + // legs_executed
+        8 + // snapshot_supply
+        8 + // created_at
+        8 + // bond
+        1 + // cancelled
+        1 + // is_initialized
+        1 // roles
+    }
+}
+
+// Layout version written as the first byte of every Vote account
+pub const VOTE_ACCOUNT_VERSION: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct VoteAccount {
+    pub version: u8,
+    pub voter: Pubkey,
+    pub vote: u8,
+    pub voting_power: u64,
+    pub is_initialized: bool,
+}
+
+impl VoteAccount {
+    pub const LEN: usize =
+        1 + // version
+        32 + // voter
+        1 + // vote
+        8 + // voting_power
+        1; // is_initialized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instruction::MAX_PROPOSAL_LEGS, utils::{MAX_ACCEPTED_MINTS, MAX_FUND_MEMBERS}};
+
+    fn zeroed<T: BorshDeserialize>(len: usize) -> T {
+        T::try_from_slice(&vec![0; len]).unwrap()
+    }
+
+    #[test]
+    fn fund_account_space_matches_borsh_size() {
+        for (num_members, num_accepted_mints) in [(0, 1), (1, 1), (MAX_FUND_MEMBERS, MAX_ACCEPTED_MINTS)] {
+            let fund_data = FundAccount {
+                version: FUND_ACCOUNT_VERSION,
+                name: [1; 32],
+                creator: Pubkey::new_unique(),
+                members: (0..num_members).map(|_| Pubkey::new_unique()).collect(),
+                total_deposit: u64::MAX,
+                governance_mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+                is_initialized: true,
+                created_at: i64::MAX,
+                is_private: 1,
+                is_paused: true,
+                deposits_frozen: true,
+                admin_nonce: u64::MAX,
+                active_proposals: u32::MAX,
+                last_proposal_at: i64::MAX,
+                config: zeroed(FundConfig::LEN),
+                accepted_mints: (0..num_accepted_mints)
+                    .map(|_| AcceptedMint { mint: Pubkey::new_unique(), price_feed: Pubkey::new_unique() })
+                    .collect(),
+            };
+            let serialized = borsh::to_vec(&fund_data).unwrap();
+            assert_eq!(serialized.len(), FundAccount::space(num_members, num_accepted_mints));
+        }
+    }
+
+    #[test]
+    fn proposal_account_space_matches_borsh_size() {
+        for num_legs in [0, 1, MAX_PROPOSAL_LEGS as usize] {
+            let proposal_data = InvestmentProposalAccount {
+                version: PROPOSAL_ACCOUNT_VERSION,
+                fund: Pubkey::new_unique(),
+                proposer: Pubkey::new_unique(),
+                proposal_kind: PROPOSAL_KIND_INVESTMENT,
+                target_member: Pubkey::new_unique(),
+                recipient: Pubkey::new_unique(),
+                lamports: u64::MAX,
+                config_change: zeroed(ConfigChange::LEN),
+                from_assets: vec![Pubkey::new_unique(); num_legs],
+                to_assets: vec![Pubkey::new_unique(); num_legs],
+                amounts: vec![u64::MAX; num_legs],
+                min_out: vec![u64::MAX; num_legs],
+                dex_tags: vec![1; num_legs],
+                votes_yes: u64::MAX,
+                votes_no: u64::MAX,
+                deadline: i64::MAX,
+                executed: true,
+                legs_executed: u8::MAX,
+                snapshot_supply: u64::MAX,
+                created_at: i64::MAX,
+                bond: u64::MAX,
+                cancelled: true,
+                is_initialized: true,
+                roles: u8::MAX,
+            };
+            let serialized = borsh::to_vec(&proposal_data).unwrap();
+            assert_eq!(serialized.len(), InvestmentProposalAccount::space(num_legs));
+        }
+    }
+
+    #[test]
+    fn fixed_size_accounts_match_their_len() {
+        assert_eq!(borsh::to_vec(&zeroed::<VaultAccount>(VaultAccount::LEN)).unwrap().len(), VaultAccount::LEN);
+        assert_eq!(borsh::to_vec(&zeroed::<UserSpecificAccount>(UserSpecificAccount::LEN)).unwrap().len(), UserSpecificAccount::LEN);
+        assert_eq!(borsh::to_vec(&zeroed::<VoteAccount>(VoteAccount::LEN)).unwrap().len(), VoteAccount::LEN);
+        assert_eq!(borsh::to_vec(&zeroed::<AcceptedMint>(AcceptedMint::LEN)).unwrap().len(), AcceptedMint::LEN);
+    }
+}
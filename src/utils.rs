@@ -0,0 +1,299 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, sysvar::{rent::Rent, Sysvar}};
+use spl_token::state::Mint;
+use crate::{dex::SUPPORTED_DEX_MASK, errors::FundError, oracle::MAX_PRICE_AGE, state::{FundConfig, VaultAccount, PROPOSAL_KIND_REMOVE_MEMBER, PROPOSAL_KIND_SET_ROLES, PROPOSAL_KIND_WITHDRAWAL, ROLE_ADMIN, ROLE_PROPOSER, VOTING_MODE_QUADRATIC, VOTING_MODE_TIME_WEIGHTED, VOTING_MODE_TOKEN_WEIGHTED}};
+
+// Accounts are only deserialized once their owner is known, a look-alike account with matching
+// data owned by another program is rejected
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+// Asset accounts must be initialized SPL mints owned by the token program
+pub fn assert_mint(account: &AccountInfo) -> ProgramResult {
+    assert_owned_by(account, &spl_token::id())?;
+    Mint::unpack(&account.data.borrow()).map_err(|_| FundError::InvalidAccountData)?;
+
+    Ok(())
+}
+
+// Program accounts passed for CPIs must be the canonical programs, not look-alikes
+pub fn assert_program(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.key != program_id {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+// Accounts the program writes to must be passed writable, otherwise the write would fail deep
+// inside serialization instead of with a clear error up front
+pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        return Err(FundError::InvalidAccountData.into());
+    }
+
+    Ok(())
+}
+
+// Seed prefix of every Fund PDA, used for both derivation and signing
+pub const FUND_SEED: &[u8] = b"fund";
+
+// Seed prefix of both the global User PDA and the Fund-specific User PDA
+pub const USER_SEED: &[u8] = b"user";
+
+// Global User PDA, one per wallet, lists the Funds the wallet belongs to
+pub fn derive_user_pda(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_SEED, wallet.as_ref()], program_id)
+}
+
+// User-specific PDA, one per (Fund, wallet), holds the member's position in that Fund
+pub fn derive_user_specific_pda(program_id: &Pubkey, fund_pda: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_SEED, fund_pda.as_ref(), wallet.as_ref()], program_id)
+}
+
+// Seed prefix of a Fund's proposal registry PDA
+pub const PROPOSAL_REGISTRY_SEED: &[u8] = b"proposals";
+
+// Proposal registry is a PDA of the Fund, created with the Fund's first proposal
+pub fn derive_proposal_registry_pda(program_id: &Pubkey, fund_pda: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_REGISTRY_SEED, fund_pda.as_ref()], program_id)
+}
+
+// Seed prefix of a Fund's governance mint PDA
+pub const GOVERNANCE_SEED: &[u8] = b"governance";
+
+// Governance mint is a PDA of the Fund, so every instruction can re-derive it
+pub fn derive_governance_mint_pda(program_id: &Pubkey, fund_pda: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GOVERNANCE_SEED, fund_pda.as_ref()], program_id)
+}
+
+// Seed prefix of a Fund's reserve account PDA
+pub const RESERVE_SEED: &[u8] = b"reserve";
+
+// Reserve is a WSOL token account at a PDA of the Fund, created with the first deposit that has a
+// reserve share
+pub fn derive_reserve_pda(program_id: &Pubkey, fund_pda: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RESERVE_SEED, fund_pda.as_ref()], program_id)
+}
+
+// Upper bound on Fund members, keeps the Fund account and per-member loops bounded
+pub const MAX_FUND_MEMBERS: usize = 20;
+
+// Upper bound on mints accepted at Fund creation, each one is an extra account in that
+// transaction and 32 bytes of Fund account, so this keeps init within one transaction
+pub const MAX_ACCEPTED_MINTS: usize = 10;
+
+// Highest deposit fee a Fund can charge, 10%
+pub const MAX_DEPOSIT_FEE_BPS: u16 = 1000;
+
+// Highest execution reward a Fund can pay per proposal, 0.1 SOL
+pub const MAX_EXECUTION_REWARD: u64 = 100_000_000;
+
+// Voting window allowed between proposal creation and its deadline
+pub const MIN_PROPOSAL_WINDOW: i64 = 60 * 60;
+pub const MAX_PROPOSAL_WINDOW: i64 = 30 * 24 * 60 * 60;
+
+// Fund's minimum voting window when its creator doesn't set one, leaves every timezone a day
+pub const DEFAULT_MIN_VOTING_PERIOD: i64 = 24 * 60 * 60;
+
+// How long a passed proposal stays executable once its execution_delay is over. Until then it
+// can't be closed, so a pending or partly executed proposal isn't wiped out from under the executor.
+pub const EXECUTION_GRACE_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+// Smallest deposit that can bootstrap a Fund's governance supply, 0.001 SOL. A dust-sized first
+// share could be inflated by donating to the vault until later deposits round down to nothing.
+pub const MIN_FIRST_DEPOSIT: u64 = 1_000_000;
+
+// How long a RefreshVaultValue result can stand in for recomputing the vault's value
+pub const VAULT_VALUE_MAX_AGE: i64 = 60;
+
+// Single place where the Fund PDA is derived from the fund name
+pub fn derive_fund_pda(program_id: &Pubkey, fund_name: &[u8]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FUND_SEED, fund_name], program_id)
+}
+
+// Checks shared by Fund creation and any later configuration change
+pub fn validate_fund_config(config: &FundConfig) -> ProgramResult {
+    if config.quorum_bps > 10000 ||
+       config.pass_threshold_bps < 5000 || config.pass_threshold_bps > 10000 ||
+       config.governance_decimals > 9 ||
+       config.lockup_seconds < 0 ||
+       config.deposit_fee_bps > MAX_DEPOSIT_FEE_BPS ||
+       config.reserve_bps > 10000 ||
+       config.dex_allowlist & !SUPPORTED_DEX_MASK != 0 ||
+       config.min_voting_period < 0 || config.min_voting_period > MAX_PROPOSAL_WINDOW ||
+       config.anti_snipe_window < 0 || config.anti_snipe_window > MAX_PROPOSAL_WINDOW ||
+       config.deadline_extension < 0 || config.deadline_extension > MAX_PROPOSAL_WINDOW ||
+       config.execution_reward > MAX_EXECUTION_REWARD ||
+       config.execution_delay < 0 || config.execution_delay > MAX_PROPOSAL_WINDOW ||
+       config.max_price_age <= 0 || config.max_price_age > MAX_PRICE_AGE {
+        return Err(FundError::InvalidFundConfig.into());
+    }
+
+    // Time-weighted voting scales power by tenure up to the cap, which must be positive
+    match config.voting_mode {
+        VOTING_MODE_TOKEN_WEIGHTED => {}
+        VOTING_MODE_TIME_WEIGHTED if config.time_weight_cap_seconds > 0 => {}
+        VOTING_MODE_QUADRATIC => {}
+        _ => return Err(FundError::InvalidFundConfig.into()),
+    }
+
+    Ok(())
+}
+
+// Deadline must leave at least the Fund's min_voting_period (never under MIN_PROPOSAL_WINDOW)
+// and at most MAX_PROPOSAL_WINDOW for voting
+pub fn validate_deadline(deadline: i64, current_time: i64, min_voting_period: i64) -> ProgramResult {
+    if deadline < current_time.saturating_add(MIN_PROPOSAL_WINDOW.max(min_voting_period)) ||
+       deadline > current_time.saturating_add(MAX_PROPOSAL_WINDOW) {
+        return Err(FundError::InvalidDeadline.into());
+    }
+
+    Ok(())
+}
+
+// Governance tokens minted for depositing `amount` lamports into a vault worth `vault_value`,
+// so the depositor buys in at the current share price. First deposit bootstraps at 1:1.
+pub fn governance_tokens_for_deposit(amount: u64, supply: u64, vault_value: u64) -> Result<u64, ProgramError> {
+    if supply == 0 || vault_value == 0 {
+        return Ok(amount);
+    }
+
+    let tokens = (amount as u128) * (supply as u128) / (vault_value as u128);
+    u64::try_from(tokens).map_err(|_| FundError::ArithmeticOverflow.into())
+}
+
+// Governance tokens burnt for withdrawing `amount` lamports, rounded up so a withdrawal never
+// takes more than the burnt share of the vault
+pub fn governance_tokens_for_withdrawal(amount: u64, supply: u64, vault_value: u64) -> Result<u64, ProgramError> {
+    if supply == 0 || vault_value == 0 {
+        return Ok(amount);
+    }
+
+    let tokens = ((amount as u128) * (supply as u128)).div_ceil(vault_value as u128);
+    u64::try_from(tokens).map_err(|_| FundError::ArithmeticOverflow.into())
+}
+
+// Voting power of `balance` governance tokens held by a member who joined at `join_time`,
+// scaled by min(tenure, cap) / cap so power grows linearly until the cap is reached
+pub fn time_weighted_power(balance: u64, join_time: i64, current_time: i64, cap_seconds: i64) -> u64 {
+    if cap_seconds <= 0 {
+        return balance;
+    }
+
+    let tenure = current_time.saturating_sub(join_time).clamp(0, cap_seconds);
+    ((balance as u128) * (tenure as u128) / (cap_seconds as u128)) as u64
+}
+
+// Integer square root, floor(sqrt(value)), by Newton's method
+pub fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+
+    let mut x = value;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+// Role a member needs to create a proposal of `proposal_kind`. Moving funds out, removing members
+// and changing roles are high-risk and need ROLE_ADMIN.
+pub fn required_role(proposal_kind: u8) -> u8 {
+    match proposal_kind {
+        PROPOSAL_KIND_WITHDRAWAL | PROPOSAL_KIND_REMOVE_MEMBER | PROPOSAL_KIND_SET_ROLES => ROLE_ADMIN,
+        _ => ROLE_PROPOSER,
+    }
+}
+
+// Native lamports the Vault PDA holds above its rent-exempt minimum
+pub fn vault_spare_lamports(vault_account_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let min_balance = Rent::get()?.minimum_balance(vault_account_info.data_len());
+    Ok(vault_account_info.lamports().saturating_sub(min_balance))
+}
+
+// Every native lamport leaving the Vault PDA goes through here. The Vault is owned by this program
+// and carries its VaultAccount record, so the system program can't transfer out of it even with the
+// Vault's seeds; the program debits it directly instead and never below rent-exemption.
+pub fn transfer_from_vault(vault_account_info: &AccountInfo, destination_info: &AccountInfo, lamports: u64) -> ProgramResult {
+    if lamports > vault_spare_lamports(vault_account_info)? {
+        return Err(FundError::InsufficientVaultFunds.into());
+    }
+
+    **vault_account_info.try_borrow_mut_lamports()? -= lamports;
+    **destination_info.try_borrow_mut_lamports()? += lamports;
+
+    Ok(())
+}
+
+// Cached vault value, if RefreshVaultValue ran within VAULT_VALUE_MAX_AGE of `now`
+pub fn fresh_vault_value(vault: &VaultAccount, now: i64) -> Option<u64> {
+    if vault.last_valuation_time > 0 && now.saturating_sub(vault.last_valuation_time) <= VAULT_VALUE_MAX_AGE {
+        Some(vault.cached_value)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_deposit_mints_one_token_per_lamport() {
+        assert_eq!(governance_tokens_for_deposit(1_000, 0, 0).unwrap(), 1_000);
+        // Lamports sitting in an otherwise empty vault don't change the bootstrap price
+        assert_eq!(governance_tokens_for_deposit(1_000, 0, 5_000).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn deposits_buy_fewer_tokens_once_the_vault_appreciates() {
+        // 1_000 tokens backed by 1_000 lamports, at par
+        assert_eq!(governance_tokens_for_deposit(500, 1_000, 1_000).unwrap(), 500);
+        // Vault doubled, the same deposit buys half as many tokens
+        assert_eq!(governance_tokens_for_deposit(500, 1_000, 2_000).unwrap(), 250);
+        // Rounded down, in the vault's favour
+        assert_eq!(governance_tokens_for_deposit(1, 1_000, 3_000).unwrap(), 0);
+        assert!(governance_tokens_for_deposit(u64::MAX, u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn withdrawals_burn_tokens_rounded_up() {
+        assert_eq!(governance_tokens_for_withdrawal(500, 1_000, 2_000).unwrap(), 250);
+        assert_eq!(governance_tokens_for_withdrawal(1, 1_000, 3_000).unwrap(), 1);
+    }
+
+    #[test]
+    fn isqrt_is_the_floor_square_root() {
+        for (value, root) in [(0, 0), (1, 1), (2, 1), (3, 1), (4, 2), (15, 3), (16, 4), (10_000, 100), (u64::MAX, u32::MAX as u64)] {
+            assert_eq!(isqrt(value), root);
+        }
+        for value in 0..100_000 {
+            let root = isqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn deadlines_must_leave_the_voting_window() {
+        let now = 1_000_000;
+        let invalid = Err(FundError::InvalidDeadline.into());
+        // Past, and too soon for even the shortest window
+        assert_eq!(validate_deadline(now - 1, now, 0), invalid);
+        assert_eq!(validate_deadline(now + MIN_PROPOSAL_WINDOW - 1, now, 0), invalid);
+        // Fund's longer min_voting_period takes over from the program's minimum
+        assert_eq!(validate_deadline(now + MIN_PROPOSAL_WINDOW, now, DEFAULT_MIN_VOTING_PERIOD), invalid);
+        assert_eq!(validate_deadline(now + MAX_PROPOSAL_WINDOW + 1, now, 0), invalid);
+
+        assert_eq!(validate_deadline(now + MIN_PROPOSAL_WINDOW, now, 0), Ok(()));
+        assert_eq!(validate_deadline(now + DEFAULT_MIN_VOTING_PERIOD, now, DEFAULT_MIN_VOTING_PERIOD), Ok(()));
+        assert_eq!(validate_deadline(now + MAX_PROPOSAL_WINDOW, now, DEFAULT_MIN_VOTING_PERIOD), Ok(()));
+    }
+}
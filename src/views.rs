@@ -0,0 +1,75 @@
+use borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+
+// Returned by GetFundSummary through the transaction's return data
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FundSummary {
+    pub member_count: u32,
+    pub total_deposit: u64,
+    pub governance_supply: u64,
+    pub active_proposal_count: u32,
+    pub is_paused: bool,
+    pub deposits_frozen: bool,
+    pub admin_nonce: u64,
+}
+
+// Returned by GetFundValuation through the transaction's return data, all in lamports except
+// supply. price_per_token is the value behind one whole governance token.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FundValuation {
+    pub supply: u64,
+    pub vault_lamports: u64,
+    pub vault_token_values: u64,
+    pub price_per_token: u64,
+}
+
+// Most members returned per GetMembers page, keeps the page within the 1024-byte return data limit
+pub const MEMBERS_PAGE_SIZE: usize = 30;
+
+// Returned by GetMembers through the transaction's return data, members[offset..offset + page]
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MembersPage {
+    pub total_members: u32,
+    pub offset: u32,
+    pub members: Vec<Pubkey>,
+}
+
+// Most proposals returned per GetOpenProposals page, same return data limit as members
+pub const PROPOSALS_PAGE_SIZE: usize = 30;
+
+// Returned by GetOpenProposals through the transaction's return data, proposals[offset..offset + page]
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProposalsPage {
+    pub total_proposals: u32,
+    pub offset: u32,
+    pub proposals: Vec<Pubkey>,
+}
+
+// Returned by GetMemberPosition through the transaction's return data
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MemberPosition {
+    pub deposit: u64,
+    pub governance_token_balance: u64,
+    // Balance currently held in the member's governance token account
+    pub governance_ata_balance: u64,
+    pub num_proposals: u8,
+    pub is_active: bool,
+    pub join_time: i64,
+    pub last_deposit_time: i64,
+}
+
+// Returned by GetProposalStatus through the transaction's return data
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum ProposalStatus {
+    // Voting open, no votes cast yet
+    Pending,
+    // Would pass if executed once voting closes (or can be executed now)
+    Passing,
+    // Voting open and the tally doesn't pass yet
+    Failing,
+    Executed,
+    // Voting closed without the proposal passing
+    Expired,
+    // Withdrawn by the proposer
+    Cancelled,
+}